@@ -0,0 +1,1220 @@
+//! Typed syntax tree built from the pest parse tree
+//!
+//! [`Statement`] mirrors the grammar so that SELECT, INSERT, UPDATE and DELETE statements can be
+//! walked structurally instead of matching on [`Rule`] values. Identifiers keep the text they
+//! were written with.
+
+use pest::iterators::{Pair, Pairs};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    normalize_gap_tokens, split_qualified, table_definition, QueryKind, Rule, TableDefinition,
+};
+
+/// Parsed SQL statement
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Statement {
+    /// SELECT, including compound SELECTs and WITH queries
+    Select(Query),
+    /// INSERT
+    Insert(InsertStatement),
+    /// UPDATE
+    Update(UpdateStatement),
+    /// DELETE
+    Delete(DeleteStatement),
+    /// CREATE TABLE
+    CreateTable(TableDefinition),
+    /// CREATE [MATERIALIZED] VIEW
+    CreateView(CreateViewStatement),
+    /// Statement without a typed representation (ALTER TABLE, DROP TABLE, CREATE/DROP INDEX, SET,
+    /// transaction control)
+    Other {
+        /// Kind of the statement
+        kind: QueryKind,
+        /// Statement text as written
+        sql: String,
+    },
+}
+
+/// Query with optional common table expressions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Query {
+    /// Common table expressions of the WITH clause
+    pub ctes: Vec<Cte>,
+    /// Whether the WITH clause is RECURSIVE
+    pub recursive: bool,
+    /// SELECT or set operation producing the rows
+    pub body: SetExpr,
+}
+
+/// Common table expression (`name (columns) AS (query)`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cte {
+    /// Name the expression is referenced by
+    pub name: String,
+    /// Explicit column names, empty when not given
+    pub columns: Vec<String>,
+    /// Defining query
+    pub query: Query,
+}
+
+/// SELECT or a tree of set operations over SELECTs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SetExpr {
+    /// Single SELECT
+    Select(Box<SelectStatement>),
+    /// UNION, EXCEPT or INTERSECT of two operands
+    SetOperation {
+        /// Set operator
+        op: SetOperator,
+        /// Whether duplicates are kept (ALL)
+        all: bool,
+        /// Left operand
+        left: Box<SetExpr>,
+        /// Right operand
+        right: Box<SetExpr>,
+    },
+}
+
+/// Set operator combining two SELECTs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SetOperator {
+    /// UNION
+    Union,
+    /// EXCEPT
+    Except,
+    /// INTERSECT
+    Intersect,
+}
+
+/// Single SELECT statement
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct SelectStatement {
+    /// Whether the projection is DISTINCT
+    pub distinct: bool,
+    /// Projected items
+    pub projection: Vec<SelectItem>,
+    /// Comma-separated FROM items
+    pub from: Vec<TableFactor>,
+    /// Explicit JOINs following the FROM items
+    pub joins: Vec<Join>,
+    /// WHERE condition
+    pub selection: Option<Expr>,
+    /// GROUP BY expressions
+    pub group_by: Vec<Expr>,
+    /// HAVING condition
+    pub having: Option<Expr>,
+    /// ORDER BY items
+    pub order_by: Vec<OrderByExpr>,
+    /// Row limit from LIMIT, TOP, or FETCH FIRST (1 when FETCH has no count)
+    pub limit: Option<u64>,
+    /// Rows skipped by OFFSET
+    pub offset: Option<u64>,
+}
+
+/// Projected item of a SELECT
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SelectItem {
+    /// All columns (`*`)
+    Wildcard,
+    /// All columns of one table (`t.*`); holds the table name
+    QualifiedWildcard(String),
+    /// Expression with optional alias
+    Expr {
+        /// Projected expression
+        expr: Expr,
+        /// Alias given with AS
+        alias: Option<String>,
+    },
+}
+
+/// Table reference in FROM or JOIN
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TableFactor {
+    /// Named table
+    Table {
+        /// Table name
+        name: String,
+        /// Alias of the table
+        alias: Option<String>,
+    },
+    /// Subquery in FROM (derived table)
+    Derived {
+        /// Query producing the rows
+        subquery: Box<Query>,
+        /// Alias of the derived table
+        alias: Option<String>,
+    },
+}
+
+/// JOIN clause
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Join {
+    /// Join type; a plain JOIN is an inner join
+    pub join_type: JoinType,
+    /// Joined table
+    pub relation: TableFactor,
+    /// ON condition
+    pub on: Option<Expr>,
+}
+
+/// Type of a JOIN
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JoinType {
+    /// INNER JOIN or plain JOIN
+    Inner,
+    /// LEFT [OUTER] JOIN
+    Left,
+    /// RIGHT [OUTER] JOIN
+    Right,
+    /// FULL [OUTER] JOIN
+    Full,
+    /// CROSS JOIN
+    Cross,
+}
+
+/// ORDER BY item
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderByExpr {
+    /// Sort expression
+    pub expr: Expr,
+    /// Whether rows are sorted descending (DESC or `USING >`)
+    pub descending: bool,
+}
+
+/// INSERT statement
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InsertStatement {
+    /// Target table
+    pub table: String,
+    /// SQLite conflict action of `INSERT OR ...`, upper-cased
+    pub or_action: Option<String>,
+    /// Inserted values
+    pub values: Vec<Expr>,
+}
+
+/// UPDATE statement
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UpdateStatement {
+    /// Target table
+    pub table: String,
+    /// Column assignments of the SET list
+    pub assignments: Vec<Assignment>,
+    /// WHERE condition
+    pub selection: Option<Expr>,
+    /// Cursor of `WHERE CURRENT OF`
+    pub current_of: Option<String>,
+}
+
+/// DELETE statement
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeleteStatement {
+    /// Target table
+    pub table: String,
+    /// WHERE condition
+    pub selection: Option<Expr>,
+    /// Cursor of `WHERE CURRENT OF`
+    pub current_of: Option<String>,
+}
+
+/// Column assignment of an UPDATE (`column = value`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Assignment {
+    /// Assigned column
+    pub column: String,
+    /// New value
+    pub value: Expr,
+}
+
+/// CREATE [MATERIALIZED] VIEW statement
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CreateViewStatement {
+    /// View name
+    pub name: String,
+    /// Whether the view is materialized
+    pub materialized: bool,
+    /// Explicit column names, empty when not given
+    pub columns: Vec<String>,
+    /// Defining query
+    pub query: Query,
+    /// Level of `WITH CHECK OPTION` (`CASCADED` or `LOCAL`)
+    pub check_option: Option<String>,
+}
+
+/// Expression
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Expr {
+    /// Column reference, optionally qualified by a table or alias
+    Column {
+        /// Table or alias before the last dot, with any schema and database prefix
+        qualifier: Option<String>,
+        /// Column name
+        name: String,
+    },
+    /// Literal value
+    Literal(Literal),
+    /// Bind parameter as written (`?`, `$1`, `:name`, `@name`)
+    Parameter(String),
+    /// Function call
+    Function(Function),
+    /// EXISTS predicate
+    Exists(Box<Query>),
+    /// Scalar subquery used as a value
+    Subquery(Box<Query>),
+    /// CASE expression
+    Case {
+        /// Operand of a simple CASE; `None` for a searched CASE
+        operand: Option<Box<Expr>>,
+        /// WHEN ... THEN ... branches in order
+        branches: Vec<CaseWhen>,
+        /// Result of the ELSE branch
+        else_result: Option<Box<Expr>>,
+    },
+    /// Parenthesized expression
+    Nested(Box<Expr>),
+    /// Prefix operator
+    Unary {
+        /// Operator
+        op: UnaryOperator,
+        /// Operand
+        expr: Box<Expr>,
+    },
+    /// Infix operator
+    Binary {
+        /// Left operand
+        left: Box<Expr>,
+        /// Operator
+        op: BinaryOperator,
+        /// Right operand
+        right: Box<Expr>,
+    },
+    /// `expr [NOT] BETWEEN low AND high`
+    Between {
+        /// Tested expression
+        expr: Box<Expr>,
+        /// Whether the predicate is NOT BETWEEN
+        negated: bool,
+        /// Lower bound
+        low: Box<Expr>,
+        /// Upper bound
+        high: Box<Expr>,
+    },
+    /// `expr [NOT] IN (list)`
+    InList {
+        /// Tested expression
+        expr: Box<Expr>,
+        /// Whether the predicate is NOT IN
+        negated: bool,
+        /// Listed values
+        list: Vec<Expr>,
+    },
+    /// `expr [NOT] IN (subquery)`
+    InSubquery {
+        /// Tested expression
+        expr: Box<Expr>,
+        /// Whether the predicate is NOT IN
+        negated: bool,
+        /// Subquery producing the values
+        subquery: Box<Query>,
+    },
+    /// `expr IS [NOT] NULL`
+    IsNull {
+        /// Tested expression
+        expr: Box<Expr>,
+        /// Whether the predicate is IS NOT NULL
+        negated: bool,
+    },
+    /// `expr IS [NOT] TRUE` or `expr IS [NOT] FALSE`
+    IsBoolean {
+        /// Tested expression
+        expr: Box<Expr>,
+        /// Whether the predicate is IS NOT
+        negated: bool,
+        /// Truth value compared against
+        value: bool,
+    },
+    /// `expr IS [NOT] DISTINCT FROM other`, a comparison that treats NULLs as equal
+    IsDistinctFrom {
+        /// Left operand
+        expr: Box<Expr>,
+        /// Whether the predicate is IS NOT DISTINCT FROM
+        negated: bool,
+        /// Right operand
+        other: Box<Expr>,
+    },
+    /// Type conversion, written `CAST(expr AS type)` or PostgreSQL-style `expr::type`
+    Cast {
+        /// Converted expression
+        expr: Box<Expr>,
+        /// Target type as written
+        data_type: String,
+    },
+}
+
+/// WHEN ... THEN ... branch of a CASE expression
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CaseWhen {
+    /// Condition, or value compared with the operand of a simple CASE
+    pub condition: Expr,
+    /// Result when the branch matches
+    pub result: Expr,
+}
+
+/// Literal value
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Literal {
+    /// Numeric literal as written
+    Number(String),
+    /// String literal without its quotes and with escapes resolved
+    String(String),
+    /// TRUE or FALSE
+    Boolean(bool),
+    /// NULL
+    Null,
+}
+
+/// Function call
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Function {
+    /// Function name, optionally schema-qualified
+    pub name: String,
+    /// Arguments in order
+    pub args: Vec<Expr>,
+    /// Whether the argument is `*` (`COUNT(*)`)
+    pub wildcard: bool,
+    /// Whether the arguments are DISTINCT
+    pub distinct: bool,
+    /// Condition of a FILTER (WHERE ...) clause
+    pub filter: Option<Box<Expr>>,
+    /// Window of an OVER clause
+    pub over: Option<WindowSpec>,
+}
+
+/// Window specification of an OVER clause
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct WindowSpec {
+    /// PARTITION BY expressions
+    pub partition_by: Vec<Expr>,
+    /// ORDER BY items
+    pub order_by: Vec<OrderByExpr>,
+    /// Frame clause; `None` for the default frame
+    pub frame: Option<WindowFrame>,
+}
+
+/// Frame of a window (`ROWS BETWEEN start AND end`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WindowFrame {
+    /// Unit the frame is measured in
+    pub units: FrameUnits,
+    /// Start of the frame
+    pub start: FrameBound,
+    /// End of the frame; `None` when only the start is given
+    pub end: Option<FrameBound>,
+}
+
+/// Unit of a window frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FrameUnits {
+    /// ROWS
+    Rows,
+    /// RANGE
+    Range,
+    /// GROUPS
+    Groups,
+}
+
+/// Start or end of a window frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FrameBound {
+    /// UNBOUNDED PRECEDING
+    UnboundedPreceding,
+    /// `n PRECEDING`
+    Preceding(u64),
+    /// CURRENT ROW
+    CurrentRow,
+    /// `n FOLLOWING`
+    Following(u64),
+    /// UNBOUNDED FOLLOWING
+    UnboundedFollowing,
+}
+
+/// Prefix operator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UnaryOperator {
+    /// `+`
+    Plus,
+    /// `-`
+    Minus,
+    /// NOT
+    Not,
+}
+
+/// Infix operator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BinaryOperator {
+    /// `+`
+    Plus,
+    /// `-`
+    Minus,
+    /// `*`
+    Multiply,
+    /// `/`
+    Divide,
+    /// `%`
+    Modulo,
+    /// `||`
+    Concat,
+    /// `=`
+    Eq,
+    /// `<>` or `!=`
+    NotEq,
+    /// MySQL NULL-safe equality `<=>`
+    NullSafeEq,
+    /// `<`
+    Lt,
+    /// `<=`
+    LtEq,
+    /// `>`
+    Gt,
+    /// `>=`
+    GtEq,
+    /// LIKE
+    Like,
+    /// NOT LIKE
+    NotLike,
+    /// SIMILAR TO
+    SimilarTo,
+    /// NOT SIMILAR TO
+    NotSimilarTo,
+    /// `~`
+    RegexMatch,
+    /// `~*`
+    RegexIMatch,
+    /// `!~`
+    RegexNotMatch,
+    /// `!~*`
+    RegexNotIMatch,
+    /// AND
+    And,
+    /// OR
+    Or,
+}
+
+impl BinaryOperator {
+    fn from_token(token: &str) -> Option<Self> {
+        Some(match token {
+            "+" => Self::Plus,
+            "-" => Self::Minus,
+            "*" => Self::Multiply,
+            "/" => Self::Divide,
+            "%" => Self::Modulo,
+            "||" => Self::Concat,
+            "=" => Self::Eq,
+            "<>" | "!=" => Self::NotEq,
+            "<=>" => Self::NullSafeEq,
+            "<" => Self::Lt,
+            "<=" => Self::LtEq,
+            ">" => Self::Gt,
+            ">=" => Self::GtEq,
+            "LIKE" => Self::Like,
+            "NOT LIKE" => Self::NotLike,
+            "SIMILAR TO" => Self::SimilarTo,
+            "NOT SIMILAR TO" => Self::NotSimilarTo,
+            "~" => Self::RegexMatch,
+            "~*" => Self::RegexIMatch,
+            "!~" => Self::RegexNotMatch,
+            "!~*" => Self::RegexNotIMatch,
+            "AND" => Self::And,
+            "OR" => Self::Or,
+            _ => return None,
+        })
+    }
+}
+
+impl Statement {
+    /// Build a statement from the pairs returned by [`crate::parse_sql`]
+    ///
+    /// # Arguments
+    /// * `pairs` - Parse tree of a single statement
+    ///
+    /// # Returns
+    /// Typed statement, or `None` when the pairs hold no statement
+    pub fn from_pairs(pairs: Pairs<Rule>) -> Option<Self> {
+        let statement = pairs
+            .flatten()
+            .find(|pair| matches!(pair.as_rule(), Rule::statement | Rule::script_statement))?;
+        let body = statement.into_inner().next()?;
+
+        Some(match body.as_rule() {
+            Rule::compound_select => Self::Select(query(body)?),
+            Rule::insert_stmt => Self::Insert(insert_statement(body)?),
+            Rule::update_stmt => Self::Update(update_statement(body)?),
+            Rule::delete_stmt => Self::Delete(delete_statement(body)?),
+            Rule::create_view_stmt => Self::CreateView(create_view_statement(body)?),
+            Rule::create_table_stmt => Self::CreateTable(table_definition(body.into_inner())),
+            rule => Self::Other {
+                kind: match rule {
+                    Rule::alter_table_stmt => QueryKind::AlterTable,
+                    Rule::drop_table_stmt => QueryKind::DropTable,
+                    Rule::create_index_stmt => QueryKind::CreateIndex,
+                    Rule::drop_index_stmt => QueryKind::DropIndex,
+                    Rule::set_session_stmt => QueryKind::Set,
+                    _ => QueryKind::Transaction,
+                },
+                sql: body.as_str().trim_end().to_string(),
+            },
+        })
+    }
+}
+
+/// Keywords between two offsets of the parsed input, upper-cased and without comments
+fn gap_text(pair: &Pair<Rule>, start: usize, end: usize) -> String {
+    normalize_gap_tokens(&pair.as_span().get_input()[start..end])
+}
+
+fn trimmed(pair: &Pair<Rule>) -> String {
+    pair.as_str().trim_end().to_string()
+}
+
+fn query(pair: Pair<Rule>) -> Option<Query> {
+    let mut ctes = Vec::new();
+    let mut recursive = false;
+    let mut body = None;
+
+    for inner in pair.into_inner() {
+        match inner.as_rule() {
+            Rule::with_clause => {
+                for item in inner.into_inner() {
+                    match item.as_rule() {
+                        Rule::recursive => recursive = true,
+                        _ => ctes.push(cte(item)?),
+                    }
+                }
+            }
+            Rule::union_clause => {
+                let start = inner.as_span().start();
+                let operand = inner.into_inner().next()?;
+                let keywords = gap_text(&operand, start, operand.as_span().start());
+                let op = if keywords.starts_with("EXCEPT") {
+                    SetOperator::Except
+                } else {
+                    SetOperator::Union
+                };
+                body = Some(SetExpr::SetOperation {
+                    op,
+                    all: keywords.ends_with("ALL"),
+                    left: Box::new(body?),
+                    right: Box::new(set_operand(operand)?),
+                });
+            }
+            _ => body = Some(set_operand(inner)?),
+        }
+    }
+
+    Some(Query {
+        ctes,
+        recursive,
+        body: body?,
+    })
+}
+
+fn cte(pair: Pair<Rule>) -> Option<Cte> {
+    let mut name = None;
+    let mut columns = Vec::new();
+    let mut definition = None;
+
+    for inner in pair.into_inner() {
+        match inner.as_rule() {
+            Rule::identifier => name = Some(trimmed(&inner)),
+            Rule::column_list => columns = identifier_list(inner),
+            _ => definition = Some(query(inner)?),
+        }
+    }
+
+    Some(Cte {
+        name: name?,
+        columns,
+        query: definition?,
+    })
+}
+
+fn set_operand(pair: Pair<Rule>) -> Option<SetExpr> {
+    if pair.as_rule() == Rule::select_stmt {
+        return Some(SetExpr::Select(Box::new(select_statement(pair)?)));
+    }
+
+    // INTERSECT chains are left-associative like the other set operators
+    let mut body = None;
+    for inner in pair.into_inner() {
+        match inner.as_rule() {
+            Rule::intersect_clause => {
+                let start = inner.as_span().start();
+                let select = inner.into_inner().next()?;
+                body = Some(SetExpr::SetOperation {
+                    op: SetOperator::Intersect,
+                    all: gap_text(&select, start, select.as_span().start()).ends_with("ALL"),
+                    left: Box::new(body?),
+                    right: Box::new(SetExpr::Select(Box::new(select_statement(select)?))),
+                });
+            }
+            _ => body = Some(SetExpr::Select(Box::new(select_statement(inner)?))),
+        }
+    }
+    body
+}
+
+fn select_statement(pair: Pair<Rule>) -> Option<SelectStatement> {
+    let mut select = SelectStatement::default();
+
+    for inner in pair.into_inner() {
+        match inner.as_rule() {
+            Rule::distinct => select.distinct = true,
+            Rule::projection => {
+                select.projection = match inner.into_inner().next() {
+                    Some(list) => list.into_inner().map(select_item).collect::<Option<_>>()?,
+                    None => vec![SelectItem::Wildcard],
+                };
+            }
+            Rule::from_item => {
+                select.from = inner
+                    .into_inner()
+                    .map(table_factor)
+                    .collect::<Option<_>>()?;
+            }
+            Rule::join_clause => select.joins.push(join(inner)?),
+            Rule::where_clause => select.selection = Some(expr(inner.into_inner().next()?)?),
+            Rule::group_by_clause => select.group_by = expr_list(inner.into_inner().next()?)?,
+            Rule::having_clause => select.having = Some(expr(inner.into_inner().next()?)?),
+            Rule::order_by_clause => select.order_by = order_by(inner)?,
+            Rule::limit_clause | Rule::top_clause => {
+                select.limit = inner.into_inner().last()?.as_str().parse().ok();
+            }
+            Rule::offset_clause => {
+                select.offset = inner.into_inner().next()?.as_str().parse().ok();
+            }
+            Rule::fetch_clause => {
+                select.limit = match inner.into_inner().next() {
+                    Some(count) => count.as_str().parse().ok(),
+                    None => Some(1),
+                };
+            }
+            _ => {}
+        }
+    }
+
+    Some(select)
+}
+
+fn select_item(pair: Pair<Rule>) -> Option<SelectItem> {
+    let mut inner = pair.into_inner();
+    let first = inner.next()?;
+
+    if first.as_rule() == Rule::qualified_wildcard {
+        let text = first.as_str();
+        return Some(SelectItem::QualifiedWildcard(
+            text[..text.len() - 2].to_string(),
+        ));
+    }
+
+    Some(SelectItem::Expr {
+        expr: expr(first)?,
+        alias: inner.next().map(|alias| trimmed(&alias)),
+    })
+}
+
+fn table_factor(pair: Pair<Rule>) -> Option<TableFactor> {
+    let mut name = None;
+    let mut subquery = None;
+    let mut alias = None;
+
+    for inner in pair.into_inner() {
+        match inner.as_rule() {
+            Rule::qualified_identifier => name = Some(trimmed(&inner)),
+            Rule::alias_identifier => alias = Some(trimmed(&inner)),
+            Rule::compound_select => subquery = Some(query(inner)?),
+            _ => {}
+        }
+    }
+
+    Some(match subquery {
+        Some(subquery) => TableFactor::Derived {
+            subquery: Box::new(subquery),
+            alias,
+        },
+        None => TableFactor::Table { name: name?, alias },
+    })
+}
+
+fn join(pair: Pair<Rule>) -> Option<Join> {
+    let mut join_type = JoinType::Inner;
+    let mut relation = None;
+    let mut on = None;
+
+    for inner in pair.into_inner() {
+        match inner.as_rule() {
+            Rule::JOIN_TYPE => {
+                join_type = match normalize_gap_tokens(inner.as_str()).split(' ').next() {
+                    Some("LEFT") => JoinType::Left,
+                    Some("RIGHT") => JoinType::Right,
+                    Some("FULL") => JoinType::Full,
+                    Some("CROSS") => JoinType::Cross,
+                    _ => JoinType::Inner,
+                };
+            }
+            Rule::table_factor => relation = Some(table_factor(inner)?),
+            _ => on = Some(expr(inner)?),
+        }
+    }
+
+    Some(Join {
+        join_type,
+        relation: relation?,
+        on,
+    })
+}
+
+fn order_by(pair: Pair<Rule>) -> Option<Vec<OrderByExpr>> {
+    pair.into_inner()
+        .flat_map(|list| list.into_inner())
+        .map(|item| {
+            let mut inner = item.into_inner();
+            let sort = expr(inner.next()?)?;
+            let descending = inner.next().is_some_and(|direction| {
+                matches!(
+                    normalize_gap_tokens(direction.as_str()).as_str(),
+                    "DESC" | ">" | ">="
+                )
+            });
+            Some(OrderByExpr {
+                expr: sort,
+                descending,
+            })
+        })
+        .collect()
+}
+
+fn identifier_list(column_list: Pair<Rule>) -> Vec<String> {
+    column_list
+        .into_inner()
+        .flat_map(|list| list.into_inner())
+        .map(|identifier| trimmed(&identifier))
+        .collect()
+}
+
+fn expr_list(pair: Pair<Rule>) -> Option<Vec<Expr>> {
+    pair.into_inner().map(expr).collect()
+}
+
+fn insert_statement(pair: Pair<Rule>) -> Option<InsertStatement> {
+    let mut table = None;
+    let mut or_action = None;
+    let mut values = Vec::new();
+
+    for inner in pair.into_inner() {
+        match inner.as_rule() {
+            Rule::insert_or => {
+                or_action = inner
+                    .into_inner()
+                    .next()
+                    .map(|action| action.as_str().trim_end().to_uppercase());
+            }
+            Rule::qualified_identifier => table = Some(trimmed(&inner)),
+            _ => values.push(expr(inner)?),
+        }
+    }
+
+    Some(InsertStatement {
+        table: table?,
+        or_action,
+        values,
+    })
+}
+
+fn update_statement(pair: Pair<Rule>) -> Option<UpdateStatement> {
+    let mut table = None;
+    let mut assignments = Vec::new();
+    let mut selection = None;
+    let mut current_of = None;
+
+    for inner in pair.into_inner() {
+        match inner.as_rule() {
+            Rule::qualified_identifier => table = Some(trimmed(&inner)),
+            Rule::set_list => {
+                for item in inner.into_inner() {
+                    let mut parts = item.into_inner();
+                    let column = trimmed(&parts.next()?);
+                    assignments.push(Assignment {
+                        column,
+                        value: expr(parts.next()?)?,
+                    });
+                }
+            }
+            Rule::where_clause => selection = Some(expr(inner.into_inner().next()?)?),
+            _ => current_of = Some(trimmed(&inner.into_inner().next()?)),
+        }
+    }
+
+    Some(UpdateStatement {
+        table: table?,
+        assignments,
+        selection,
+        current_of,
+    })
+}
+
+fn delete_statement(pair: Pair<Rule>) -> Option<DeleteStatement> {
+    let mut table = None;
+    let mut selection = None;
+    let mut current_of = None;
+
+    for inner in pair.into_inner() {
+        match inner.as_rule() {
+            Rule::qualified_identifier => table = Some(trimmed(&inner)),
+            Rule::where_clause => selection = Some(expr(inner.into_inner().next()?)?),
+            _ => current_of = Some(trimmed(&inner.into_inner().next()?)),
+        }
+    }
+
+    Some(DeleteStatement {
+        table: table?,
+        selection,
+        current_of,
+    })
+}
+
+fn create_view_statement(pair: Pair<Rule>) -> Option<CreateViewStatement> {
+    let mut name = None;
+    let mut materialized = false;
+    let mut columns = Vec::new();
+    let mut definition = None;
+    let mut check_option = None;
+
+    for inner in pair.into_inner() {
+        match inner.as_rule() {
+            Rule::qualified_identifier => name = Some(trimmed(&inner)),
+            Rule::materialized => materialized = true,
+            Rule::column_list => columns = identifier_list(inner),
+            Rule::compound_select => definition = Some(query(inner)?),
+            _ => {
+                check_option = Some(inner.into_inner().next().map_or_else(
+                    || "CASCADED".to_string(),
+                    |level| level.as_str().trim_end().to_uppercase(),
+                ));
+            }
+        }
+    }
+
+    Some(CreateViewStatement {
+        name: name?,
+        materialized,
+        columns,
+        query: definition?,
+        check_option,
+    })
+}
+
+fn expr(pair: Pair<Rule>) -> Option<Expr> {
+    match pair.as_rule() {
+        Rule::or_expr | Rule::and_expr | Rule::addition | Rule::multiplication => {
+            binary_chain(pair)
+        }
+        Rule::not_expr => {
+            let start = pair.as_span().start();
+            let comparison = pair.into_inner().next()?;
+            let negated = comparison.as_span().start() > start;
+            let operand = expr(comparison)?;
+            Some(if negated {
+                Expr::Unary {
+                    op: UnaryOperator::Not,
+                    expr: Box::new(operand),
+                }
+            } else {
+                operand
+            })
+        }
+        Rule::comparison => {
+            let mut inner = pair.into_inner();
+            let mut left = expr(inner.next()?)?;
+            for suffix in inner {
+                left = comparison_suffix(left, suffix)?;
+            }
+            Some(left)
+        }
+        Rule::unary => unary(pair),
+        Rule::primary => {
+            let inner = pair.into_inner().next()?;
+            if inner.as_rule() == Rule::expr {
+                return Some(Expr::Nested(Box::new(expr(inner)?)));
+            }
+            expr(inner)
+        }
+        Rule::literal => Some(Expr::Literal(literal(pair))),
+        Rule::parameter => Some(Expr::Parameter(trimmed(&pair))),
+        Rule::niladic_function => Some(Expr::Function(Function {
+            name: pair.as_str().to_uppercase(),
+            args: Vec::new(),
+            wildcard: false,
+            distinct: false,
+            filter: None,
+            over: None,
+        })),
+        Rule::function_call => function(pair),
+        Rule::case_expr => case(pair),
+        Rule::cast_expr => {
+            let mut inner = pair.into_inner();
+            Some(Expr::Cast {
+                expr: Box::new(expr(inner.next()?)?),
+                data_type: trimmed(&inner.next()?),
+            })
+        }
+        Rule::exists_expr => Some(Expr::Exists(Box::new(query(pair.into_inner().next()?)?))),
+        Rule::subquery_expr => Some(Expr::Subquery(Box::new(query(pair.into_inner().next()?)?))),
+        Rule::column => {
+            let text = pair.as_str();
+            let mut parts = split_qualified(text);
+            let name = parts.pop()?;
+            Some(Expr::Column {
+                qualifier: (!parts.is_empty()).then(|| parts.join(".")),
+                name,
+            })
+        }
+        _ => expr(pair.into_inner().next()?),
+    }
+}
+
+/// Fold a left-associative operator chain; operators are read from the text between operands
+fn binary_chain(pair: Pair<Rule>) -> Option<Expr> {
+    let mut operands = pair
+        .into_inner()
+        .filter(|inner| inner.as_rule() != Rule::concat_op);
+    let first = operands.next()?;
+    let mut end = first.as_span().end();
+    let mut left = expr(first)?;
+
+    for operand in operands {
+        let op = BinaryOperator::from_token(&gap_text(&operand, end, operand.as_span().start()))?;
+        end = operand.as_span().end();
+        left = Expr::Binary {
+            left: Box::new(left),
+            op,
+            right: Box::new(expr(operand)?),
+        };
+    }
+
+    Some(left)
+}
+
+fn comparison_suffix(left: Expr, suffix: Pair<Rule>) -> Option<Expr> {
+    let start = suffix.as_span().start();
+    let end = suffix.as_span().end();
+    let mut inner = suffix.clone().into_inner().peekable();
+
+    if let Some(op) = inner.next_if(|first| first.as_rule() == Rule::comp_op) {
+        return Some(Expr::Binary {
+            left: Box::new(left),
+            op: BinaryOperator::from_token(&normalize_gap_tokens(op.as_str()))?,
+            right: Box::new(expr(inner.next()?)?),
+        });
+    }
+
+    let keywords_end = inner.peek().map_or(end, |first| first.as_span().start());
+    let keywords = gap_text(&suffix, start, keywords_end);
+    let negated = keywords.contains("NOT");
+    let left = Box::new(left);
+
+    if keywords.contains("BETWEEN") {
+        Some(Expr::Between {
+            expr: left,
+            negated,
+            low: Box::new(expr(inner.next()?)?),
+            high: Box::new(expr(inner.next()?)?),
+        })
+    } else if keywords.contains("DISTINCT") {
+        Some(Expr::IsDistinctFrom {
+            expr: left,
+            negated,
+            other: Box::new(expr(inner.next()?)?),
+        })
+    } else if keywords.starts_with("IS") {
+        Some(match keywords.rsplit(' ').next() {
+            Some("TRUE") => Expr::IsBoolean {
+                expr: left,
+                negated,
+                value: true,
+            },
+            Some("FALSE") => Expr::IsBoolean {
+                expr: left,
+                negated,
+                value: false,
+            },
+            _ => Expr::IsNull {
+                expr: left,
+                negated,
+            },
+        })
+    } else {
+        let Some(values) = inner.next().and_then(|rhs| rhs.into_inner().next()) else {
+            return Some(Expr::InList {
+                expr: left,
+                negated,
+                list: Vec::new(),
+            });
+        };
+        Some(match values.as_rule() {
+            Rule::compound_select => Expr::InSubquery {
+                expr: left,
+                negated,
+                subquery: Box::new(query(values)?),
+            },
+            _ => Expr::InList {
+                expr: left,
+                negated,
+                list: expr_list(values)?,
+            },
+        })
+    }
+}
+
+fn unary(pair: Pair<Rule>) -> Option<Expr> {
+    let start = pair.as_span().start();
+    let mut inner = pair.into_inner();
+    let primary = inner.next()?;
+    let signs = gap_text(&primary, start, primary.as_span().start());
+    let mut operand = expr(primary)?;
+
+    // Casts bind tighter than the sign prefix
+    for cast in inner {
+        operand = Expr::Cast {
+            expr: Box::new(operand),
+            data_type: trimmed(&cast.into_inner().next()?),
+        };
+    }
+    for sign in signs.split(' ').filter(|sign| !sign.is_empty()).rev() {
+        operand = Expr::Unary {
+            op: if sign == "-" {
+                UnaryOperator::Minus
+            } else {
+                UnaryOperator::Plus
+            },
+            expr: Box::new(operand),
+        };
+    }
+
+    Some(operand)
+}
+
+fn literal(pair: Pair<Rule>) -> Literal {
+    match pair.into_inner().next() {
+        Some(value) => match value.as_rule() {
+            Rule::number => Literal::Number(value.as_str().to_string()),
+            Rule::string => {
+                let text = value.as_str();
+                Literal::String(
+                    text[1..text.len() - 1]
+                        .replace("\\'", "'")
+                        .replace("\\\\", "\\"),
+                )
+            }
+            _ => Literal::Boolean(value.as_str().trim_end().eq_ignore_ascii_case("TRUE")),
+        },
+        None => Literal::Null,
+    }
+}
+
+fn function(pair: Pair<Rule>) -> Option<Expr> {
+    let end = pair.as_span().end();
+    let mut inner = pair.clone().into_inner();
+    let name = inner.next()?;
+    let mut function = Function {
+        name: name.as_str().to_string(),
+        args: Vec::new(),
+        wildcard: false,
+        distinct: false,
+        filter: None,
+        over: None,
+    };
+
+    let mut rest = inner.peekable();
+    let arguments_end = rest.peek().map_or(end, |next| next.as_span().start());
+    function.wildcard = gap_text(&pair, name.as_span().end(), arguments_end).contains('*');
+
+    for argument in rest {
+        match argument.as_rule() {
+            Rule::distinct => function.distinct = true,
+            Rule::filter_clause => {
+                function.filter = Some(Box::new(expr(argument.into_inner().next()?)?));
+            }
+            Rule::window_spec => {
+                let mut window = WindowSpec::default();
+                for clause in argument.into_inner() {
+                    match clause.as_rule() {
+                        Rule::partition_by_clause => {
+                            window.partition_by = expr_list(clause.into_inner().next()?)?;
+                        }
+                        Rule::window_frame => window.frame = Some(window_frame(clause)?),
+                        _ => window.order_by = order_by(clause)?,
+                    }
+                }
+                function.over = Some(window);
+            }
+            _ => function.args.push(expr(argument)?),
+        }
+    }
+
+    Some(Expr::Function(function))
+}
+
+fn case(pair: Pair<Rule>) -> Option<Expr> {
+    let mut operand = None;
+    let mut branches = Vec::new();
+    let mut else_result = None;
+
+    for inner in pair.into_inner() {
+        match inner.as_rule() {
+            Rule::case_operand => operand = Some(Box::new(expr(inner.into_inner().next()?)?)),
+            Rule::when_clause => {
+                let mut parts = inner.into_inner();
+                branches.push(CaseWhen {
+                    condition: expr(parts.next()?)?,
+                    result: expr(parts.next()?)?,
+                });
+            }
+            _ => else_result = Some(Box::new(expr(inner.into_inner().next()?)?)),
+        }
+    }
+
+    Some(Expr::Case {
+        operand,
+        branches,
+        else_result,
+    })
+}
+
+fn window_frame(pair: Pair<Rule>) -> Option<WindowFrame> {
+    let mut inner = pair.into_inner();
+    let units = match normalize_gap_tokens(inner.next()?.as_str()).as_str() {
+        "RANGE" => FrameUnits::Range,
+        "GROUPS" => FrameUnits::Groups,
+        _ => FrameUnits::Rows,
+    };
+    let start = frame_bound(inner.next()?)?;
+    let end = match inner.next() {
+        Some(bound) => Some(frame_bound(bound)?),
+        None => None,
+    };
+
+    Some(WindowFrame { units, start, end })
+}
+
+fn frame_bound(pair: Pair<Rule>) -> Option<FrameBound> {
+    let text = normalize_gap_tokens(pair.as_str());
+    let (amount, direction) = text.split_once(' ')?;
+
+    Some(match (amount, direction) {
+        ("UNBOUNDED", "PRECEDING") => FrameBound::UnboundedPreceding,
+        ("UNBOUNDED", _) => FrameBound::UnboundedFollowing,
+        ("CURRENT", _) => FrameBound::CurrentRow,
+        (rows, "PRECEDING") => FrameBound::Preceding(rows.parse().ok()?),
+        (rows, _) => FrameBound::Following(rows.parse().ok()?),
+    })
+}
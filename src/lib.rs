@@ -1,10 +1,13 @@
 use pest::Parser;
 use pest_derive::Parser;
 use serde::{Deserialize, Serialize};
+use std::cell::OnceCell;
 use std::collections::{HashMap, HashSet};
 
 pub use pest::iterators::Pairs;
 
+pub mod ast;
+
 /// Main SQL parser struct using pest grammar
 #[derive(Parser)]
 #[grammar = "grammar/grammar.pest"]
@@ -12,9 +15,18 @@ pub struct SqlParser;
 
 /// Metadata extracted from SQL query parsing containing tables, columns, functions, etc.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
 pub struct QueryMetadata {
-    /// Set of table names referenced in the query
+    /// Kind of statement the metadata was extracted from
+    pub kind: QueryKind,
+    /// Set of table names referenced in the query, keeping any `schema.` or `database.schema.`
+    /// prefix they were written with
     pub tables: HashSet<String>,
+    /// Schema qualifiers of the referenced tables (`public`, `analytics.public`, ...)
+    pub schemas: HashSet<String>,
+    /// Identifiers that were written quoted, without their quotes; their spelling is exact rather
+    /// than folded to the dialect's case (see [`tables_match`])
+    pub quoted_identifiers: HashSet<String>,
     /// Set of column names referenced in the query
     pub columns: HashSet<String>,
     /// Map of table/column aliases (alias -> original name)
@@ -25,6 +37,597 @@ pub struct QueryMetadata {
     pub aggregates: HashSet<String>,
     /// List of JOIN operations with their details
     pub joins: Vec<JoinInfo>,
+    /// Index hints attached to table references (table -> hint text)
+    pub index_hints: Vec<(String, String)>,
+    /// Optimizer hints written as `/*+ ... */` comments, without the comment markers
+    pub hints: Vec<String>,
+    /// Comparisons found in WHERE conditions
+    pub where_predicates: Vec<Predicate>,
+    /// Comparisons found in HAVING conditions
+    pub having_predicates: Vec<Predicate>,
+    /// Literal values (numbers, strings, NULL, booleans) as written in expressions
+    pub literals: Vec<String>,
+    /// Bind parameter placeholders (`?`, `$1`, `:name`, `@name`) in order of appearance
+    pub parameters: Vec<Parameter>,
+    /// Derived tables in FROM/JOIN (alias -> tables read by the subquery)
+    pub derived_tables: HashMap<String, HashSet<String>>,
+    /// Aliases naming a derived table rather than a base table
+    pub derived_aliases: HashSet<String>,
+    /// Window function calls with their OVER clause details
+    pub window_functions: Vec<WindowFunction>,
+    /// Aggregate function names used with an OVER clause
+    pub windowed_aggregates: HashSet<String>,
+    /// Aggregate function names used with a FILTER (WHERE ...) clause
+    pub filtered_aggregates: HashSet<String>,
+    /// Aggregate function names applied to DISTINCT arguments, as in `COUNT(DISTINCT x)`
+    pub distinct_aggregates: HashSet<String>,
+    /// ORDER BY items of every SELECT in order of appearance
+    pub order_by: Vec<OrderByItem>,
+    /// GROUP BY expressions of every SELECT as written, in order of appearance
+    pub group_by: Vec<String>,
+    /// Row count of the outermost SELECT's LIMIT, FETCH FIRST, or TOP clause
+    pub limit: Option<u64>,
+    /// Raw trailing options of CREATE TABLE (TABLESPACE, WITH (...), INHERITS (...))
+    pub table_options: Vec<String>,
+    /// Names of common table expressions defined in WITH clauses
+    pub cte_names: HashSet<String>,
+    /// Column references split into table and column, with aliases resolved to table names;
+    /// `table.*` projections are recorded with column `*`
+    pub qualified_columns: Vec<QualifiedColumn>,
+    /// Column references with the qualifier they were written with and, when it is unambiguous,
+    /// the base table they belong to
+    pub resolved_columns: Vec<ColumnRef>,
+    /// Target types of `CAST(... AS type)` and `::` casts as written (`int[]`, `numeric(10,2)`,
+    /// ...), in order of appearance
+    pub casts: Vec<String>,
+    /// Byte range `(start, end)` of the statement in a script analyzed with
+    /// [`analyze_statements`]; `None` for single-statement analysis
+    pub statement_span: Option<(usize, usize)>,
+    /// Cursor named by `WHERE CURRENT OF` in a positioned UPDATE or DELETE
+    pub cursor: Option<String>,
+    /// View created by a CREATE [MATERIALIZED] VIEW statement
+    pub view: Option<ViewInfo>,
+    /// Table defined by a CREATE TABLE statement
+    pub table_definition: Option<TableDefinition>,
+    /// Index names created or dropped by CREATE INDEX or DROP INDEX
+    pub indexes: HashSet<String>,
+    /// Conflict action of a SQLite `INSERT OR ...` statement, uppercased (`REPLACE`, `IGNORE`, ...)
+    pub insert_or_action: Option<String>,
+    /// Whether the statement replaces existing rows on conflict, as `INSERT OR REPLACE` does
+    pub is_upsert: bool,
+    /// Query text with keywords uppercased and whitespace and comments collapsed; only set when
+    /// requested through [`AnalyzeOptions::store_normalized`]
+    pub normalized_sql: Option<String>,
+}
+
+impl QueryMetadata {
+    /// Reset the table and join fields when they are not selected by `fields`
+    ///
+    /// The analysis needs tables, aliases and joins to resolve the names it collects, so these
+    /// are always gathered and only dropped afterwards; other field groups are skipped during
+    /// the analysis itself.
+    fn retain_fields(&mut self, fields: MetadataFields) {
+        if !fields.contains(MetadataFields::TABLES) {
+            self.tables.clear();
+            self.schemas.clear();
+            self.quoted_identifiers.clear();
+            self.aliases.clear();
+            self.index_hints.clear();
+            self.hints.clear();
+            self.derived_tables.clear();
+            self.derived_aliases.clear();
+            self.table_options.clear();
+            self.cte_names.clear();
+            self.indexes.clear();
+        }
+        if !fields.contains(MetadataFields::JOINS) {
+            self.joins.clear();
+        }
+    }
+}
+
+bitflags::bitflags! {
+    /// Groups of metadata fields that an analysis should collect
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    pub struct MetadataFields: u8 {
+        /// Tables, table aliases, derived tables, CTE names, index and optimizer hints, table
+        /// options, and index names
+        const TABLES = 1;
+        /// Column references, GROUP BY expressions, and ORDER BY items
+        const COLUMNS = 1 << 1;
+        /// Function and aggregate names, including window functions, and cast types
+        const FUNCTIONS = 1 << 2;
+        /// JOIN operations
+        const JOINS = 1 << 3;
+        /// Literal values and bind parameters
+        const LITERALS = 1 << 4;
+        /// WHERE and HAVING predicates
+        const PREDICATES = 1 << 5;
+    }
+}
+
+/// A single comparison from a WHERE or HAVING condition
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Predicate {
+    /// Left-hand operand as written
+    pub left: String,
+    /// Comparison operator (=, <, <=>, LIKE, IN, BETWEEN, IS NULL, IS NOT TRUE, IS DISTINCT FROM,
+    /// etc.)
+    pub operator: String,
+    /// Right-hand operand as written, if the operator takes one
+    pub right: Option<String>,
+}
+
+/// View defined by a CREATE [MATERIALIZED] VIEW statement
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ViewInfo {
+    /// Name of the view
+    pub name: String,
+    /// Column names listed after the view name, if any
+    pub columns: Vec<String>,
+    /// Level of a `WITH CHECK OPTION` clause, uppercased: `LOCAL` or `CASCADED` (the default when
+    /// no level is written)
+    pub check_option: Option<String>,
+    /// Whether the view is a MATERIALIZED VIEW
+    #[serde(default)]
+    pub materialized: bool,
+    /// Base tables read by the defining query, including joined tables and excluding CTEs
+    #[serde(default)]
+    pub source_tables: HashSet<String>,
+}
+
+/// Table defined by a CREATE TABLE statement
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
+pub struct TableDefinition {
+    /// Name of the table
+    pub name: String,
+    /// Column definitions in order
+    pub columns: Vec<ColumnDefinition>,
+    /// Primary key columns, from a column or table constraint
+    pub primary_key: Vec<String>,
+    /// Column sets of UNIQUE constraints
+    pub unique: Vec<Vec<String>>,
+    /// Foreign keys, from REFERENCES clauses or FOREIGN KEY constraints
+    pub foreign_keys: Vec<ForeignKey>,
+}
+
+/// Column of a CREATE TABLE statement
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ColumnDefinition {
+    /// Column name
+    pub name: String,
+    /// Data type as written (`VARCHAR(64)`, `TEXT[]`, ...)
+    pub data_type: String,
+    /// Whether the column accepts NULL; `false` for NOT NULL and primary key columns
+    pub nullable: bool,
+    /// DEFAULT value as written
+    pub default: Option<String>,
+}
+
+/// Foreign key of a CREATE TABLE statement
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ForeignKey {
+    /// Referencing columns of the defined table
+    pub columns: Vec<String>,
+    /// Referenced table
+    pub referenced_table: String,
+    /// Referenced columns; empty when the referenced table's primary key is implied
+    pub referenced_columns: Vec<String>,
+}
+
+/// A column reference with its table qualifier
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct QualifiedColumn {
+    /// Table the column belongs to (alias resolved), if the reference is qualified
+    pub table: Option<String>,
+    /// Column name, or `*` for a `table.*` projection
+    pub column: String,
+}
+
+/// Column reference resolved against the tables a query reads
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct ColumnRef {
+    /// Column name without its qualifier
+    pub name: String,
+    /// Table or alias the column was qualified with, as written
+    pub qualifier: Option<String>,
+    /// Base table the column belongs to; `None` when the qualifier names a derived table or CTE,
+    /// or an unqualified column could come from more than one table
+    pub table: Option<String>,
+}
+
+/// Placeholder syntax of a bind parameter
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum ParameterStyle {
+    /// Anonymous `?`
+    Question,
+    /// Numbered `$1`, `$2`, ...
+    Dollar,
+    /// Named `:name`
+    Colon,
+    /// Named `@name`
+    At,
+}
+
+/// Bind parameter placeholder with its position in the query text
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct Parameter {
+    /// Placeholder syntax
+    pub style: ParameterStyle,
+    /// Name without its `:` or `@` prefix, for named placeholders
+    pub name: Option<String>,
+    /// Number of a `$N` placeholder, or the 1-based ordinal of a `?` among the query's `?`s
+    pub index: Option<usize>,
+    /// Byte range `(start, end)` of the placeholder in the input
+    pub span: (usize, usize),
+}
+
+/// Kind of name a [`Symbol`] refers to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum SymbolKind {
+    /// Table name
+    Table,
+    /// Alias given to a table or derived table
+    TableAlias,
+    /// Column name
+    Column,
+}
+
+/// Table, alias, or column name with its position in the query text
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Symbol {
+    /// Name as written
+    pub name: String,
+    /// What the name refers to
+    pub kind: SymbolKind,
+    /// Table the name resolves to (the table itself, the aliased table, or a column's table),
+    /// if known
+    pub table: Option<String>,
+    /// Byte range of the name in the query text
+    pub span: std::ops::Range<usize>,
+}
+
+/// Structure of a compound SELECT, with INTERSECT grouped before UNION and EXCEPT
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum SetOpTree {
+    /// Single SELECT branch as written
+    Select(String),
+    /// Set operation combining two operands
+    Operation {
+        /// Operator, uppercased (UNION, UNION ALL, EXCEPT, INTERSECT, ...)
+        operator: String,
+        /// Left operand
+        left: Box<SetOpTree>,
+        /// Right operand
+        right: Box<SetOpTree>,
+    },
+}
+
+/// A single column of a SELECT's result
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OutputColumn {
+    /// Result column name: the alias, else the column name, else the function name;
+    /// `None` for other expressions and wildcards
+    pub name: Option<String>,
+    /// Projected expression as written (`*` or `table.*` for wildcards)
+    pub expr: String,
+    /// Explicit alias given with AS
+    pub alias: Option<String>,
+}
+
+/// A single ORDER BY item
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OrderByItem {
+    /// Sort expression as written
+    pub expr: String,
+    /// Explicit direction, uppercased (ASC or DESC)
+    pub direction: Option<String>,
+    /// Ordering operator of a PostgreSQL `USING` clause (`<`, `>`, ...)
+    pub using_operator: Option<String>,
+}
+
+/// A function call evaluated over a window (`OVER (...)`)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WindowFunction {
+    /// Function name as written
+    pub function: String,
+    /// PARTITION BY expressions (empty when the window is not partitioned)
+    pub partition_by: Vec<String>,
+    /// ORDER BY items (empty when the window is unordered)
+    pub order_by: Vec<String>,
+    /// Frame clause with keywords uppercased, e.g. `ROWS BETWEEN UNBOUNDED PRECEDING AND
+    /// CURRENT ROW`; `None` when the window uses the default frame
+    #[serde(default)]
+    pub frame: Option<String>,
+}
+
+/// Errors produced while parsing or analyzing SQL
+#[derive(Debug, thiserror::Error)]
+#[allow(clippy::large_enum_variant)]
+pub enum ParseError {
+    /// Input does not match the SQL grammar
+    #[error(transparent)]
+    Syntax(#[from] pest::error::Error<Rule>),
+    /// Input is valid SQL but uses a construct forbidden by the analysis options
+    #[error("{message}")]
+    Policy {
+        /// Description of the violated policy
+        message: String,
+        /// Byte range of the offending construct in the input
+        span: std::ops::Range<usize>,
+    },
+    /// Input uses a construct the grammar is known not to support yet
+    #[error("unsupported SQL construct: {feature}")]
+    Unsupported {
+        /// Name of the construct, such as "RETURNING clauses"
+        feature: String,
+        /// Byte range of the keyword where parsing stopped
+        span: std::ops::Range<usize>,
+    },
+    /// Metadata could not be serialized or written as JSON
+    #[error("JSON serialization error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Query text whose metadata is analyzed on first use and cached afterwards
+#[derive(Debug)]
+pub struct PreparedQuery {
+    sql: String,
+    metadata: OnceCell<Result<QueryMetadata, ParseError>>,
+}
+
+impl PreparedQuery {
+    /// Create a prepared query from SQL text without parsing it yet
+    pub fn new(sql: impl Into<String>) -> Self {
+        Self {
+            sql: sql.into(),
+            metadata: OnceCell::new(),
+        }
+    }
+
+    /// SQL text of the query
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    /// Metadata for the query, analyzing it on the first call only
+    #[allow(clippy::result_large_err)]
+    pub fn metadata(&self) -> Result<&QueryMetadata, &ParseError> {
+        self.metadata
+            .get_or_init(|| analyze_sql(&self.sql).map_err(ParseError::from))
+            .as_ref()
+    }
+}
+
+impl From<&str> for PreparedQuery {
+    fn from(sql: &str) -> Self {
+        Self::new(sql)
+    }
+}
+
+impl From<String> for PreparedQuery {
+    fn from(sql: String) -> Self {
+        Self::new(sql)
+    }
+}
+
+/// Owned parse tree that does not borrow the parsed input
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseTree {
+    /// Top-level nodes of the tree
+    pub roots: Vec<ParseNode>,
+}
+
+/// Node of an owned parse tree
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseNode {
+    /// Grammar rule that produced the node
+    pub rule: Rule,
+    /// Input text matched by the node
+    pub text: String,
+    /// Byte range of the node in the parsed input
+    pub span: std::ops::Range<usize>,
+    /// Child nodes in order
+    pub children: Vec<ParseNode>,
+}
+
+impl ParseNode {
+    fn from_pair(pair: pest::iterators::Pair<Rule>) -> Self {
+        let span = pair.as_span();
+        Self {
+            rule: pair.as_rule(),
+            text: pair.as_str().to_string(),
+            span: span.start()..span.end(),
+            children: pair.into_inner().map(Self::from_pair).collect(),
+        }
+    }
+}
+
+/// Analyzed script of semicolon-separated statements
+///
+/// Iterating a script yields the metadata of each statement in order. A script that fails to
+/// parse yields a single error.
+#[derive(Debug)]
+pub struct Script {
+    statements: Vec<Result<QueryMetadata, ParseError>>,
+}
+
+impl Script {
+    /// Parse and analyze every statement of a script
+    pub fn parse(input: &str) -> Self {
+        let statements = match analyze_statements(input) {
+            Ok(statements) => statements.into_iter().map(Ok).collect(),
+            Err(err) => vec![Err(classify_syntax_error(input, err))],
+        };
+        Self { statements }
+    }
+}
+
+impl IntoIterator for Script {
+    type Item = Result<QueryMetadata, ParseError>;
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.statements.into_iter()
+    }
+}
+
+/// Shape of a query as computed by [`normalize_sql`]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct NormalizedQuery {
+    /// Query text with literals replaced by `?`, keywords lowercased, and whitespace collapsed
+    pub shape: String,
+    /// Stable 64-bit FNV-1a hash of the shape
+    pub hash: u64,
+}
+
+/// Summary of the bind parameters a query expects
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct BindSummary {
+    /// Number of anonymous `?` placeholders
+    pub anonymous_count: usize,
+    /// Highest `$N` index used (0 when there are no numbered placeholders)
+    pub max_positional: usize,
+    /// Names of `:name` / `@name` placeholders without their prefix
+    pub named: HashSet<String>,
+}
+
+/// Where the outermost SELECT uses aggregate functions, and whether it groups its rows
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct AggUsage {
+    /// An aggregate appears in the projection
+    pub in_projection: bool,
+    /// An aggregate appears in the HAVING condition
+    pub in_having: bool,
+    /// An aggregate appears in an ORDER BY item
+    pub in_order_by: bool,
+    /// The SELECT has a GROUP BY clause
+    pub has_group_by: bool,
+}
+
+/// Column types keyed by `table.column`, as used by [`infer_output_types`]
+pub type Schema = HashMap<String, String>;
+
+/// Options controlling how a query is parsed and analyzed
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnalyzeOptions {
+    /// SQL dialect whose vendor-specific syntax should be accepted: identifier quote styles (see
+    /// [`Dialect::identifier_quotes`]), `LIMIT` versus SQL Server's `TOP`, PostgreSQL `::` casts,
+    /// index hints, and similar extensions
+    pub dialect: Dialect,
+    /// Accept common generator mistakes such as an empty `IN ()` list instead of rejecting them
+    pub lenient: bool,
+    /// Metadata fields to collect; unselected fields are left empty
+    pub collect: MetadataFields,
+    /// Reject comma-separated FROM items in favour of explicit JOIN syntax
+    pub forbid_implicit_joins: bool,
+    /// Store the normalized query text in [`QueryMetadata::normalized_sql`]
+    pub store_normalized: bool,
+}
+
+impl Default for AnalyzeOptions {
+    fn default() -> Self {
+        Self {
+            dialect: Dialect::default(),
+            lenient: false,
+            collect: MetadataFields::all(),
+            forbid_implicit_joins: false,
+            store_normalized: false,
+        }
+    }
+}
+
+/// SQL dialect controlling which vendor-specific syntax is accepted
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
+pub enum Dialect {
+    /// Standard SQL without vendor extensions
+    #[default]
+    Generic,
+    /// PostgreSQL
+    Postgres,
+    /// MySQL / MariaDB
+    MySql,
+    /// SQLite
+    Sqlite,
+    /// Microsoft SQL Server
+    MsSql,
+}
+
+/// Placeholder written in place of each literal by [`sanitize_with`]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
+pub enum PlaceholderStyle {
+    /// Anonymous `?`
+    #[default]
+    Question,
+    /// Numbered `$1`, `$2`, ...
+    Dollar,
+    /// Named `:p1`, `:p2`, ...
+    Colon,
+}
+
+/// Kind of SQL statement
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
+pub enum QueryKind {
+    /// SELECT, including compound SELECTs and WITH queries
+    #[default]
+    Select,
+    /// INSERT
+    Insert,
+    /// UPDATE
+    Update,
+    /// DELETE
+    Delete,
+    /// CREATE TABLE
+    CreateTable,
+    /// CREATE [MATERIALIZED] VIEW
+    CreateView,
+    /// ALTER TABLE
+    AlterTable,
+    /// DROP TABLE
+    DropTable,
+    /// CREATE INDEX
+    CreateIndex,
+    /// DROP INDEX
+    DropIndex,
+    /// Session setting such as `SET search_path = public`
+    Set,
+    /// Transaction control (BEGIN, COMMIT, ROLLBACK)
+    Transaction,
+}
+
+impl std::str::FromStr for Dialect {
+    type Err = String;
+
+    /// Parse a dialect name as used on the command line (`ansi`, `postgres`, `mysql`, `sqlite`,
+    /// `mssql`), ignoring case
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_lowercase().as_str() {
+            "ansi" => Ok(Dialect::Generic),
+            "postgres" => Ok(Dialect::Postgres),
+            "mysql" => Ok(Dialect::MySql),
+            "sqlite" => Ok(Dialect::Sqlite),
+            "mssql" => Ok(Dialect::MsSql),
+            _ => Err(format!(
+                "unknown dialect '{name}'; expected ansi, postgres, mysql, sqlite, or mssql"
+            )),
+        }
+    }
+}
+
+impl Dialect {
+    /// Opening quote characters the dialect accepts around identifiers
+    ///
+    /// PostgreSQL only accepts `"name"`, MySQL adds `` `name` ``, SQL Server adds `[name]`, and
+    /// SQLite and standard SQL analysis accept all three.
+    pub fn identifier_quotes(self) -> &'static [char] {
+        match self {
+            Dialect::Postgres => &['"'],
+            Dialect::MySql => &['"', '`'],
+            Dialect::MsSql => &['"', '['],
+            Dialect::Generic | Dialect::Sqlite => &['"', '`', '['],
+        }
+    }
 }
 
 /// Information about a JOIN operation in the query
@@ -38,6 +641,26 @@ pub struct JoinInfo {
     pub alias: Option<String>,
     /// ON condition for the JOIN
     pub condition: String,
+    /// Column pairs compared with `=` in the ON condition
+    #[serde(default)]
+    pub on_columns: Vec<(String, String)>,
+    /// ON condition with runs of whitespace collapsed to single spaces
+    #[serde(default)]
+    pub normalized_condition: String,
+    /// Whether every top-level conjunct of the ON condition is an equality; see
+    /// [`JoinInfo::is_equi_join`]
+    #[serde(default)]
+    pub equi_join: bool,
+}
+
+impl JoinInfo {
+    /// Check whether every top-level conjunct of the ON condition is an equality
+    ///
+    /// `a.id = b.id AND a.kind = 'x'` is an equi-join; a condition containing OR, a range such as
+    /// `BETWEEN`, or no condition at all is not.
+    pub fn is_equi_join(&self) -> bool {
+        self.equi_join
+    }
 }
 
 /// Parse SQL query and return the parse tree
@@ -51,7 +674,120 @@ pub struct JoinInfo {
 pub fn parse_sql(
     input: &str,
 ) -> Result<pest::iterators::Pairs<'_, Rule>, pest::error::Error<Rule>> {
-    SqlParser::parse(Rule::sql, input)
+    parse_with_options(input, &AnalyzeOptions::default())
+}
+
+/// Parse SQL query written in a specific dialect and return the parse tree
+///
+/// # Arguments
+/// * `input` - SQL query string to parse
+/// * `dialect` - SQL dialect whose vendor-specific syntax should be accepted
+///
+/// # Returns
+/// Parse tree pairs on success, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn parse_sql_with_dialect(
+    input: &str,
+    dialect: Dialect,
+) -> Result<pest::iterators::Pairs<'_, Rule>, pest::error::Error<Rule>> {
+    let options = AnalyzeOptions {
+        dialect,
+        ..AnalyzeOptions::default()
+    };
+    parse_with_options(input, &options)
+}
+
+/// Parse SQL query into an owned tree that can outlive the input
+///
+/// # Arguments
+/// * `input` - SQL query string to parse
+///
+/// # Returns
+/// Owned parse tree on success, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn parse_to_tree(input: &str) -> Result<ParseTree, ParseError> {
+    let pairs = parse_sql(input).map_err(|err| classify_syntax_error(input, err))?;
+
+    Ok(ParseTree {
+        roots: pairs.map(ParseNode::from_pair).collect(),
+    })
+}
+
+/// Parse SQL query into a typed syntax tree
+///
+/// # Arguments
+/// * `input` - SQL query string to parse
+///
+/// # Returns
+/// Typed statement on success, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn parse_ast(input: &str) -> Result<ast::Statement, pest::error::Error<Rule>> {
+    parse_ast_with_dialect(input, Dialect::Generic)
+}
+
+/// Parse SQL query written in a specific dialect into a typed syntax tree
+///
+/// Vendor syntax is only accepted by its dialect, so fields such as a `TOP` row limit, a SQLite
+/// `INSERT OR` action, or a PostgreSQL `USING >` sort order are filled in here.
+///
+/// # Arguments
+/// * `input` - SQL query string to parse
+/// * `dialect` - SQL dialect whose vendor-specific syntax should be accepted
+///
+/// # Returns
+/// Typed statement on success, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn parse_ast_with_dialect(
+    input: &str,
+    dialect: Dialect,
+) -> Result<ast::Statement, pest::error::Error<Rule>> {
+    let pairs = parse_sql_with_dialect(input, dialect)?;
+    ast::Statement::from_pairs(pairs).ok_or_else(|| {
+        pest::error::Error::new_from_span(
+            pest::error::ErrorVariant::CustomError {
+                message: "parse tree has no typed representation".to_string(),
+            },
+            pest::Span::new(input, 0, input.len()).unwrap(),
+        )
+    })
+}
+
+/// Render the parse tree as indented text
+///
+/// Each line holds the rule name and byte span of a node, indented by depth. Leaf nodes also
+/// show their matched text, e.g. `identifier [7..9] "id"`.
+///
+/// # Arguments
+/// * `input` - SQL query string to parse
+///
+/// # Returns
+/// Tree text with one node per line, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn print_tree(input: &str) -> Result<String, pest::error::Error<Rule>> {
+    print_tree_with_dialect(input, Dialect::Generic)
+}
+
+/// Render the parse tree of a query written in a specific dialect as indented text
+///
+/// Uses the same layout as [`print_tree`].
+///
+/// # Arguments
+/// * `input` - SQL query string to parse
+/// * `dialect` - SQL dialect whose vendor-specific syntax should be accepted
+///
+/// # Returns
+/// Tree text with one node per line, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn print_tree_with_dialect(
+    input: &str,
+    dialect: Dialect,
+) -> Result<String, pest::error::Error<Rule>> {
+    let pairs = parse_sql_with_dialect(input, dialect)?;
+    let mut out = String::new();
+    for pair in pairs {
+        write_tree_node(pair, 0, &mut out);
+    }
+    Ok(out)
 }
 
 /// Analyze SQL query and extract metadata (tables, columns, functions, etc.)
@@ -63,279 +799,4151 @@ pub fn parse_sql(
 /// QueryMetadata struct with extracted information, or parsing error
 #[allow(clippy::result_large_err)]
 pub fn analyze_sql(input: &str) -> Result<QueryMetadata, pest::error::Error<Rule>> {
-    let pairs = SqlParser::parse(Rule::sql, input)?;
-    let mut metadata = QueryMetadata::default();
+    let pairs = parse_sql(input)?;
+
+    Ok(analyze_tree(pairs))
+}
+
+/// Analyze a script of semicolon-separated statements
+///
+/// Session settings such as `SET search_path = public` and transaction control statements are
+/// accepted and produce metadata with `kind` set to [`QueryKind::Set`] or
+/// [`QueryKind::Transaction`] and no tables.
+///
+/// # Arguments
+/// * `input` - SQL script to analyze
+///
+/// # Returns
+/// Metadata for each statement in order, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn analyze_statements(input: &str) -> Result<Vec<QueryMetadata>, pest::error::Error<Rule>> {
+    let pairs = SqlParser::parse(Rule::script, input)?;
+    validate_parsed(pairs.clone(), &AnalyzeOptions::default())?;
+
+    Ok(pairs
+        .flat_map(|script| script.into_inner())
+        .filter(|pair| pair.as_rule() == Rule::script_statement)
+        .map(|statement| {
+            let start = statement.as_span().start();
+            let end = start + statement.as_str().trim_end().len();
+            let mut metadata = analyze_tree(statement.into_inner());
+            metadata.statement_span = Some((start, end));
+            metadata
+        })
+        .collect())
+}
+
+/// Analyze SQL query and list its table, alias, and column names with their text spans
+///
+/// Qualified columns such as `u.id` produce two symbols: the qualifier, resolved through the
+/// query's aliases, and the column. Unqualified columns are not resolved to a table.
+///
+/// # Arguments
+/// * `input` - SQL query string to analyze
+///
+/// # Returns
+/// Symbols in order of appearance, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn analyze_with_spans(input: &str) -> Result<Vec<Symbol>, pest::error::Error<Rule>> {
+    let pairs = parse_sql(input)?;
+    let metadata = analyze_tree(pairs.clone());
+    let mut symbols = Vec::new();
+
+    collect_symbols(pairs, &metadata, &mut symbols);
+
+    Ok(symbols)
+}
+
+/// Find the table, alias, or column name at a byte offset, for editor hover and go-to-definition
+///
+/// # Arguments
+/// * `input` - SQL query string to inspect
+/// * `offset` - Byte offset into `input`
+///
+/// # Returns
+/// The symbol whose span contains `offset`, or `None` when there is none or the query does not
+/// parse
+pub fn symbols_at(input: &str, offset: usize) -> Option<Symbol> {
+    analyze_with_spans(input)
+        .ok()?
+        .into_iter()
+        .find(|symbol| symbol.span.contains(&offset))
+}
+
+/// Convert a byte span into 1-based line and column positions for display
+///
+/// Columns count characters rather than bytes, and both `\n` and `\r\n` end a line. Offsets past
+/// the end of `input` are clamped to it.
+///
+/// # Arguments
+/// * `input` - Text the span refers to
+/// * `span` - Byte range `(start, end)` into `input`
+///
+/// # Returns
+/// `((start_line, start_column), (end_line, end_column))`
+pub fn span_to_line_col(input: &str, span: (usize, usize)) -> ((usize, usize), (usize, usize)) {
+    let line_col = |offset: usize| {
+        let mut offset = offset.min(input.len());
+        while !input.is_char_boundary(offset) {
+            offset -= 1;
+        }
+
+        let before = &input[..offset];
+        let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+        let line = before.matches('\n').count() + 1;
+        let column = before[line_start..].trim_end_matches('\r').chars().count() + 1;
+        (line, column)
+    };
+
+    (line_col(span.0), line_col(span.1))
+}
+
+/// Rename a column of one table throughout a query
+///
+/// Qualified references are renamed when their qualifier resolves to `table`; unqualified
+/// references only when `table` is the single table the query reads. Same-named columns of
+/// other tables are left untouched. A replacement that is a reserved keyword or not a plain
+/// identifier is written as an ANSI quoted identifier (see [`quote_identifier`]); use
+/// [`rename_column_with`] to quote it for another dialect.
+///
+/// # Arguments
+/// * `input` - SQL query string to rewrite
+/// * `table` - Table owning the column
+/// * `old` - Current column name
+/// * `new` - Replacement column name
+///
+/// # Returns
+/// Rewritten query text, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn rename_column(
+    input: &str,
+    table: &str,
+    old: &str,
+    new: &str,
+) -> Result<String, pest::error::Error<Rule>> {
+    rename_column_with(input, table, old, new, Dialect::Generic)
+}
+
+/// Rename a column of one table throughout a query written in a specific dialect
+///
+/// Works like [`rename_column`], but accepts the vendor-specific syntax of `dialect` and quotes
+/// the replacement the way that dialect expects.
+///
+/// # Arguments
+/// * `input` - SQL query string to rewrite
+/// * `table` - Table owning the column
+/// * `old` - Current column name
+/// * `new` - Replacement column name
+/// * `dialect` - SQL dialect the query is written in
+///
+/// # Returns
+/// Rewritten query text, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn rename_column_with(
+    input: &str,
+    table: &str,
+    old: &str,
+    new: &str,
+    dialect: Dialect,
+) -> Result<String, pest::error::Error<Rule>> {
+    let options = AnalyzeOptions {
+        dialect,
+        ..AnalyzeOptions::default()
+    };
+    let pairs = parse_with_options(input, &options)?;
+    let metadata = analyze_tree(pairs.clone());
+    let mut symbols = Vec::new();
+    collect_symbols(pairs, &metadata, &mut symbols);
+
+    let sole_table =
+        metadata.joins.is_empty() && metadata.tables.len() == 1 && metadata.tables.contains(table);
+
+    let mut output = String::with_capacity(input.len());
+    let mut cursor = 0;
+    for symbol in symbols {
+        let owned = match symbol.table.as_deref() {
+            Some(owner) => owner == table,
+            None => sole_table,
+        };
+        if symbol.kind == SymbolKind::Column && symbol.name == old && owned {
+            output.push_str(&input[cursor..symbol.span.start]);
+            output.push_str(&quote_identifier(new, dialect));
+            cursor = symbol.span.end;
+        }
+    }
+    output.push_str(&input[cursor..]);
+
+    Ok(output)
+}
+
+/// Quote an identifier when it could not be written bare
+///
+/// Names that are reserved keywords or contain characters other than letters, digits, `_`, and
+/// `$` are quoted the way `dialect` expects: backticks for MySQL, brackets for SQL Server, and
+/// double quotes otherwise, doubling any embedded closing quote. Names that are already quoted
+/// are returned unchanged.
+///
+/// # Arguments
+/// * `name` - Identifier to write
+/// * `dialect` - SQL dialect the identifier is written for
+///
+/// # Returns
+/// The identifier, quoted if needed
+pub fn quote_identifier(name: &str, dialect: Dialect) -> String {
+    let bare = SqlParser::parse(Rule::alias_identifier, name)
+        .ok()
+        .and_then(|mut pairs| pairs.next())
+        .is_some_and(|pair| pair.as_str() == name);
+    if bare {
+        return name.to_string();
+    }
+
+    let (open, close) = match dialect {
+        Dialect::MySql => ('`', '`'),
+        Dialect::MsSql => ('[', ']'),
+        Dialect::Generic | Dialect::Postgres | Dialect::Sqlite => ('"', '"'),
+    };
+    let escaped = name.replace(close, &format!("{close}{close}"));
+    format!("{open}{escaped}{close}")
+}
+
+/// Analyze SQL query written in a specific dialect and extract metadata
+///
+/// # Arguments
+/// * `input` - SQL query string to analyze
+/// * `dialect` - SQL dialect whose vendor-specific syntax should be accepted
+///
+/// # Returns
+/// QueryMetadata struct with extracted information, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn analyze_sql_with_dialect(
+    input: &str,
+    dialect: Dialect,
+) -> Result<QueryMetadata, ParseError> {
+    let options = AnalyzeOptions {
+        dialect,
+        ..AnalyzeOptions::default()
+    };
+    analyze_sql_opts(input, &options)
+}
+
+/// Analyze SQL query and add its metadata to an existing metadata struct
+///
+/// Lets callers accumulate metadata over many queries without merging results afterward. Set and
+/// map fields gain the query's entries, and list fields have them appended. `kind` describes the
+/// last query analyzed; other single-valued fields keep the last value any query set.
+///
+/// # Arguments
+/// * `input` - SQL query string to analyze
+/// * `meta` - Metadata to add to
+///
+/// # Returns
+/// Unit on success, or parsing error (leaving `meta` unchanged)
+#[allow(clippy::result_large_err)]
+pub fn analyze_into(input: &str, meta: &mut QueryMetadata) -> Result<(), ParseError> {
+    let pairs = parse_sql(input).map_err(|err| classify_syntax_error(input, err))?;
+
+    analyze_tree_into(pairs, meta, MetadataFields::all());
+
+    Ok(())
+}
+
+/// Analyze SQL query using explicit parsing options
+///
+/// # Arguments
+/// * `input` - SQL query string to analyze
+/// * `options` - Dialect and strictness settings
+///
+/// # Returns
+/// QueryMetadata struct with extracted information, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn analyze_sql_opts(
+    input: &str,
+    options: &AnalyzeOptions,
+) -> Result<QueryMetadata, ParseError> {
+    let pairs =
+        parse_with_options(input, options).map_err(|err| classify_syntax_error(input, err))?;
+
+    if options.forbid_implicit_joins {
+        if let Some(span) = find_implicit_join(pairs.clone()) {
+            return Err(ParseError::Policy {
+                message: "implicit comma join; use explicit JOIN syntax".to_string(),
+                span,
+            });
+        }
+    }
+
+    let mut metadata = QueryMetadata::default();
+    analyze_tree_into(pairs.clone(), &mut metadata, options.collect);
+    metadata.retain_fields(options.collect);
+    if options.store_normalized {
+        metadata.normalized_sql = Some(render_canonical(input, pairs, LiteralStyle::Keep));
+    }
+
+    Ok(metadata)
+}
+
+/// Find `IN ()` predicates with an empty value list
+///
+/// The query is parsed leniently so that generated SQL containing empty lists can be inspected.
+///
+/// # Arguments
+/// * `input` - SQL query string to analyze
+///
+/// # Returns
+/// Text of each predicate with an empty IN list, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn empty_in_lists(input: &str) -> Result<Vec<String>, pest::error::Error<Rule>> {
+    let options = AnalyzeOptions {
+        lenient: true,
+        ..AnalyzeOptions::default()
+    };
+    let pairs = parse_with_options(input, &options)?;
+    let mut predicates = Vec::new();
+
+    collect_empty_in_lists(pairs, &mut predicates);
+
+    Ok(predicates)
+}
+
+/// Analyze SQL query and return metadata as pretty-printed JSON
+///
+/// # Arguments
+/// * `input` - SQL query string to analyze
+///
+/// # Returns
+/// JSON string with query metadata, or parsing/serialization error
+#[allow(clippy::result_large_err)]
+pub fn analyze_sql_json(input: &str) -> Result<String, pest::error::Error<Rule>> {
+    let metadata = analyze_sql(input)?;
+    let json = serde_json::to_string_pretty(&metadata).map_err(|e| {
+        pest::error::Error::new_from_span(
+            pest::error::ErrorVariant::CustomError {
+                message: format!("JSON serialization error: {}", e),
+            },
+            pest::Span::new(input, 0, input.len()).unwrap(),
+        )
+    })?;
+    Ok(json)
+}
+
+/// Analyze SQL query and stream its metadata as pretty-printed JSON to a writer
+///
+/// # Arguments
+/// * `input` - SQL query string to analyze
+/// * `writer` - Destination for the JSON document (file, socket, buffer, ...)
+///
+/// # Returns
+/// Unit on success, or parsing/serialization error
+#[allow(clippy::result_large_err)]
+pub fn write_analysis_json<W: std::io::Write>(
+    input: &str,
+    writer: &mut W,
+) -> Result<(), ParseError> {
+    let metadata = analyze_sql(input).map_err(|err| classify_syntax_error(input, err))?;
+    serde_json::to_writer_pretty(writer, &metadata)?;
+    Ok(())
+}
+
+/// Load query metadata previously saved as JSON
+///
+/// Fields missing from the JSON take their default value, so metadata saved by older versions
+/// still loads after new fields are added.
+///
+/// # Arguments
+/// * `json` - JSON produced by [`analyze_sql_json`] or by serializing [`QueryMetadata`]
+///
+/// # Returns
+/// Deserialized metadata, or JSON error
+pub fn metadata_from_json(json: &str) -> Result<QueryMetadata, serde_json::Error> {
+    serde_json::from_str(json)
+}
+
+/// Render a parse error as a terminal diagnostic with the offending source highlighted
+///
+/// # Arguments
+/// * `input` - SQL query string that produced the error
+/// * `err` - Error returned while parsing `input`
+///
+/// # Returns
+/// Plain-text report with the error location underlined
+#[cfg(feature = "diagnostics")]
+pub fn render_diagnostic(input: &str, err: &ParseError) -> String {
+    use ariadne::{CharSet, Config, IndexType, Label, Report, ReportKind, Source};
+
+    let (range, message) = match err {
+        ParseError::Syntax(error) => {
+            let range = match error.location {
+                pest::error::InputLocation::Pos(pos) => {
+                    let token_len = input[pos..]
+                        .find(char::is_whitespace)
+                        .unwrap_or(input.len() - pos);
+                    pos..pos + token_len
+                }
+                pest::error::InputLocation::Span((start, end)) => start..end,
+            };
+            (range, error.variant.message().into_owned())
+        }
+        ParseError::Policy { message, span } => (span.clone(), message.clone()),
+        ParseError::Unsupported { span, .. } => (span.clone(), err.to_string()),
+        ParseError::Json(error) => (0..input.len(), error.to_string()),
+    };
+
+    let mut output = Vec::new();
+    Report::build(ReportKind::Error, range.clone())
+        .with_config(
+            Config::default()
+                .with_color(false)
+                .with_char_set(CharSet::Ascii)
+                .with_index_type(IndexType::Byte),
+        )
+        .with_message(&message)
+        .with_label(Label::new(range).with_message(message))
+        .finish()
+        .write(Source::from(input), &mut output)
+        .expect("writing a report to memory cannot fail");
+
+    String::from_utf8_lossy(&output).into_owned()
+}
+
+/// Summarize the bind parameters used by a query
+///
+/// # Arguments
+/// * `input` - SQL query string to analyze
+///
+/// # Returns
+/// BindSummary with placeholder counts and names, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn bind_param_summary(input: &str) -> Result<BindSummary, pest::error::Error<Rule>> {
+    let pairs = parse_sql(input)?;
+    let mut summary = BindSummary::default();
+
+    collect_bind_params(pairs, &mut summary);
+
+    Ok(summary)
+}
+
+/// Find subqueries that are ordered without limiting their rows
+///
+/// An ORDER BY inside a subquery has no effect on the outer result unless it is paired with
+/// LIMIT, OFFSET, or FETCH, and many optimizers silently drop it.
+///
+/// # Arguments
+/// * `input` - SQL query string to analyze
+///
+/// # Returns
+/// Text of each subquery with a pointless ORDER BY, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn pointless_subquery_order(input: &str) -> Result<Vec<String>, pest::error::Error<Rule>> {
+    let pairs = parse_sql(input)?;
+    let mut subqueries = Vec::new();
+
+    collect_pointless_orders(pairs, false, &mut subqueries);
+
+    Ok(subqueries)
+}
+
+/// Find equality conditions linking correlated subqueries to their enclosing query
+///
+/// # Arguments
+/// * `input` - SQL query string to analyze
+///
+/// # Returns
+/// `(outer_column, inner_column)` pairs in order of appearance, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn correlation_pairs(input: &str) -> Result<Vec<(String, String)>, pest::error::Error<Rule>> {
+    let pairs = parse_sql(input)?;
+    let mut correlations = Vec::new();
+
+    collect_correlations(pairs, &mut Vec::new(), &mut correlations);
+
+    Ok(correlations)
+}
+
+/// Count the columns produced by the outermost SELECT
+///
+/// # Arguments
+/// * `input` - SQL query string to analyze
+///
+/// # Returns
+/// Number of projection items, `None` when a `*` or `table.*` makes the count unknown, or
+/// parsing error
+#[allow(clippy::result_large_err)]
+pub fn projected_column_count(input: &str) -> Result<Option<usize>, pest::error::Error<Rule>> {
+    let pairs = parse_sql(input)?;
+
+    let Some(projection) = leading_select(pairs).and_then(|select| {
+        select
+            .into_inner()
+            .find(|pair| pair.as_rule() == Rule::projection)
+    }) else {
+        return Ok(None);
+    };
+    let Some(list) = projection.into_inner().next() else {
+        return Ok(None);
+    };
+
+    let mut count = 0;
+    for item in list.into_inner() {
+        if item
+            .clone()
+            .into_inner()
+            .next()
+            .map(|first| first.as_rule())
+            == Some(Rule::qualified_wildcard)
+        {
+            return Ok(None);
+        }
+        count += 1;
+    }
+
+    Ok(Some(count))
+}
+
+/// Find the driving table of a SELECT: the first base table in its FROM clause
+///
+/// # Arguments
+/// * `input` - SQL query string to analyze
+///
+/// # Returns
+/// Table name (never an alias), `None` when the query has no FROM clause or starts from a
+/// derived table, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn driving_table(input: &str) -> Result<Option<String>, pest::error::Error<Rule>> {
+    let pairs = parse_sql(input)?;
+
+    let table = leading_select(pairs)
+        .and_then(|select| {
+            select
+                .into_inner()
+                .find(|pair| pair.as_rule() == Rule::from_item)
+        })
+        .and_then(|from_item| from_item.into_inner().next())
+        .and_then(|table_factor| table_factor.into_inner().next())
+        .filter(|first| first.as_rule() == Rule::qualified_identifier)
+        .map(|identifier| identifier.as_str().to_string());
+
+    Ok(table)
+}
+
+/// List the result columns of the outermost SELECT
+///
+/// # Arguments
+/// * `input` - SQL query string to analyze
+///
+/// # Returns
+/// Projection items in order, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn output_columns(input: &str) -> Result<Vec<OutputColumn>, pest::error::Error<Rule>> {
+    let pairs = parse_sql(input)?;
+
+    Ok(leading_select(pairs)
+        .map(select_output_columns)
+        .unwrap_or_default())
+}
+
+/// Map each common table expression to the tables and CTEs its body reads
+///
+/// Following the edges from the main query backwards gives the order in which the CTEs must be
+/// evaluated. A recursive CTE lists itself among its own dependencies.
+///
+/// # Arguments
+/// * `input` - SQL query string to analyze
+///
+/// # Returns
+/// CTE name -> names read by its body, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn cte_dependencies(
+    input: &str,
+) -> Result<HashMap<String, HashSet<String>>, pest::error::Error<Rule>> {
+    let pairs = parse_sql(input)?;
+    let mut dependencies = HashMap::new();
+
+    for cte in pairs.flatten().filter(|pair| pair.as_rule() == Rule::cte) {
+        let mut inner = cte.into_inner();
+        let Some(name) = inner.next() else {
+            continue;
+        };
+
+        // Without the enclosing CTE names in scope, references to them are recorded as tables
+        let mut body = QueryMetadata::default();
+        analyze_pairs(
+            inner,
+            &mut body,
+            MetadataFields::TABLES | MetadataFields::JOINS,
+        );
+
+        let mut reads = body.tables;
+        reads.extend(
+            body.joins
+                .into_iter()
+                .map(|join| join.table)
+                .filter(|table| !body.derived_aliases.contains(table)),
+        );
+        reads.extend(body.derived_tables.into_values().flatten());
+        dependencies.insert(name.as_str().to_string(), reads);
+    }
+
+    Ok(dependencies)
+}
+
+/// Describe the result columns of the whole query
+///
+/// Unlike [`output_columns`], a leading `SELECT * FROM source` is looked through when the source
+/// is a derived table or a common table expression: the columns then come from the source's own
+/// leading branch, renamed by the CTE's column list when it has one.
+///
+/// # Arguments
+/// * `input` - SQL query string to analyze
+///
+/// # Returns
+/// Result columns in order, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn result_columns(input: &str) -> Result<Vec<OutputColumn>, pest::error::Error<Rule>> {
+    let pairs = parse_sql(input)?;
+
+    Ok(pairs
+        .flatten()
+        .find(|pair| pair.as_rule() == Rule::compound_select)
+        .map(|compound| compound_result_columns(compound, &HashMap::new()))
+        .unwrap_or_default())
+}
+
+/// Describe how the outermost query combines its SELECT branches
+///
+/// INTERSECT binds tighter than UNION and EXCEPT, so `a UNION b INTERSECT c` yields a UNION
+/// whose right operand is the INTERSECT of `b` and `c`. Operators of the same precedence
+/// associate to the left.
+///
+/// # Arguments
+/// * `input` - SQL query string to analyze
+///
+/// # Returns
+/// Set operation tree, `None` when the statement is not a SELECT, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn set_op_tree(input: &str) -> Result<Option<SetOpTree>, pest::error::Error<Rule>> {
+    let pairs = parse_sql(input)?;
+
+    let Some(compound) = pairs
+        .flatten()
+        .find(|pair| pair.as_rule() == Rule::compound_select)
+    else {
+        return Ok(None);
+    };
+
+    let mut tree = None;
+    let parts = compound
+        .into_inner()
+        .filter(|part| part.as_rule() != Rule::with_clause);
+    for part in parts {
+        tree = match tree {
+            Some(left) => Some(set_operation(left, part)),
+            None => Some(set_operand_tree(part)),
+        };
+    }
+
+    Ok(tree)
+}
+
+/// Find result column names that appear more than once in the outermost SELECT
+///
+/// A projection such as `SELECT COUNT(*) AS c, c FROM t` produces two columns named `c`, which
+/// makes lookups by name ambiguous. Names are compared case-insensitively.
+///
+/// # Arguments
+/// * `input` - SQL query string to check
+///
+/// # Returns
+/// Each colliding name once, in order of first appearance, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn duplicate_output_names(input: &str) -> Result<Vec<String>, pest::error::Error<Rule>> {
+    let mut seen = HashSet::new();
+    let mut duplicates: Vec<String> = Vec::new();
+
+    for name in output_columns(input)?
+        .into_iter()
+        .filter_map(|column| column.name)
+    {
+        let key = name.to_lowercase();
+        if !seen.insert(key.clone())
+            && !duplicates
+                .iter()
+                .any(|duplicate| duplicate.to_lowercase() == key)
+        {
+            duplicates.push(name);
+        }
+    }
+
+    Ok(duplicates)
+}
+
+/// Find output aliases of the outermost SELECT that its WHERE clause references
+///
+/// WHERE is evaluated before the projection, so most databases reject `SELECT a + b AS s FROM t
+/// WHERE s > 0` while some accept it. Aliases that merely repeat the projected column's own name
+/// are ignored, as are qualified references. Names are compared case-insensitively.
+///
+/// # Arguments
+/// * `input` - SQL query string to check
+///
+/// # Returns
+/// Each referenced alias once, in order of appearance in WHERE, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn where_uses_output_alias(input: &str) -> Result<Vec<String>, pest::error::Error<Rule>> {
+    let pairs = parse_sql(input)?;
+    let Some(select) = leading_select(pairs) else {
+        return Ok(Vec::new());
+    };
+
+    let aliases: Vec<String> = select_output_columns(select.clone())
+        .into_iter()
+        .filter_map(|column| {
+            let alias = column.alias?;
+            (!alias.eq_ignore_ascii_case(&column.expr)).then_some(alias)
+        })
+        .collect();
+
+    let mut referenced: Vec<String> = Vec::new();
+    let columns = select
+        .into_inner()
+        .filter(|pair| pair.as_rule() == Rule::where_clause)
+        .flat_map(|where_clause| where_clause.into_inner().flatten())
+        .filter(|pair| pair.as_rule() == Rule::column);
+    for column in columns {
+        let name = column.as_str().trim_end();
+        if let Some(alias) = aliases
+            .iter()
+            .find(|alias| alias.eq_ignore_ascii_case(name))
+        {
+            if !referenced.contains(alias) {
+                referenced.push(alias.clone());
+            }
+        }
+    }
+
+    Ok(referenced)
+}
+
+/// Report where the outermost SELECT uses aggregate functions
+///
+/// Aggregates inside subqueries are not counted toward the outer SELECT.
+///
+/// # Arguments
+/// * `input` - SQL query string to analyze
+///
+/// # Returns
+/// Aggregate usage per clause, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn aggregate_usage_report(input: &str) -> Result<AggUsage, pest::error::Error<Rule>> {
+    let pairs = parse_sql(input)?;
+    let mut usage = AggUsage::default();
+
+    let Some(select) = leading_select(pairs) else {
+        return Ok(usage);
+    };
+    for pair in select.into_inner() {
+        let mut columns = Vec::new();
+        let flag = match pair.as_rule() {
+            Rule::projection => &mut usage.in_projection,
+            Rule::having_clause => &mut usage.in_having,
+            Rule::order_by_clause => &mut usage.in_order_by,
+            Rule::group_by_clause => {
+                usage.has_group_by = true;
+                continue;
+            }
+            _ => continue,
+        };
+        collect_unaggregated_columns(pair.into_inner(), &mut columns, flag);
+    }
+
+    Ok(usage)
+}
+
+/// Find projected columns that are neither grouped nor aggregated
+///
+/// Mirrors MySQL's `ONLY_FULL_GROUP_BY`: in a SELECT with GROUP BY (or with aggregates and no
+/// GROUP BY), every column in the projection must be grouped or used inside an aggregate.
+/// Columns passed to scalar functions such as `UPPER(name)` are checked as well.
+///
+/// # Arguments
+/// * `input` - SQL query string to check
+///
+/// # Returns
+/// Offending columns in order of appearance, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn strict_mode_check(input: &str) -> Result<Vec<String>, pest::error::Error<Rule>> {
+    let pairs = parse_sql(input)?;
+    let mut violations = Vec::new();
+
+    collect_ungrouped_columns(pairs, &mut violations);
+
+    Ok(violations)
+}
+
+/// Check whether any SELECT applies DISTINCT to a projection that always yields one row
+///
+/// `SELECT DISTINCT COUNT(*) FROM t` has a single aggregate and no GROUP BY, so it returns
+/// exactly one row and the DISTINCT does nothing.
+///
+/// # Arguments
+/// * `input` - SQL query string to check
+///
+/// # Returns
+/// `true` when a redundant DISTINCT is found, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn redundant_distinct(input: &str) -> Result<bool, pest::error::Error<Rule>> {
+    let pairs = parse_sql(input)?;
+
+    Ok(pairs
+        .flatten()
+        .filter(|pair| pair.as_rule() == Rule::select_stmt)
+        .any(has_redundant_distinct))
+}
+
+/// Check whether the query only counts rows, as in `SELECT COUNT(*) FROM t WHERE ...`
+///
+/// The projection must be a single COUNT call (any argument, including `DISTINCT x`) without a
+/// window, in a plain top-level SELECT without GROUP BY or set operations. Other statements,
+/// such as `CREATE VIEW ... AS SELECT COUNT(*) ...`, are never count queries.
+///
+/// # Arguments
+/// * `input` - SQL query string to check
+///
+/// # Returns
+/// `true` for a count-only query, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn is_count_query(input: &str) -> Result<bool, pest::error::Error<Rule>> {
+    let pairs = parse_sql(input)?;
+
+    let Some(compound) = pairs
+        .flatten()
+        .find(|pair| pair.as_rule() == Rule::statement)
+        .and_then(|statement| statement.into_inner().next())
+        .filter(|body| body.as_rule() == Rule::compound_select)
+    else {
+        return Ok(false);
+    };
+    let mut branches = compound
+        .into_inner()
+        .filter(|pair| pair.as_rule() != Rule::with_clause);
+    let (Some(select), None) = (branches.next(), branches.next()) else {
+        return Ok(false);
+    };
+
+    Ok(select.as_rule() == Rule::select_stmt
+        && sole_aggregate(select).is_some_and(|name| {
+            name.rsplit('.')
+                .next()
+                .is_some_and(|name| name.eq_ignore_ascii_case("COUNT"))
+        }))
+}
+
+/// Measure the longest OR chain of equality predicates on a single column
+///
+/// `a = 1 OR a = 2 OR a = 3` is usually better written as `a IN (1, 2, 3)`. Within each OR
+/// chain, equality branches are grouped by column and the largest group is counted.
+///
+/// # Arguments
+/// * `input` - SQL query string to analyze
+///
+/// # Returns
+/// Largest number of same-column equality branches in one OR chain (0 when there are none),
+/// or parsing error
+#[allow(clippy::result_large_err)]
+pub fn max_or_chain_length(input: &str) -> Result<usize, pest::error::Error<Rule>> {
+    let pairs = parse_sql(input)?;
+
+    Ok(pairs
+        .flatten()
+        .filter(|pair| pair.as_rule() == Rule::or_expr)
+        .map(|chain| {
+            let mut counts: HashMap<String, usize> = HashMap::new();
+            for column in chain.into_inner().filter_map(equality_column) {
+                *counts.entry(column).or_default() += 1;
+            }
+            counts.into_values().max().unwrap_or(0)
+        })
+        .max()
+        .unwrap_or(0))
+}
+
+/// Count the comparison and logical operators used in the query's predicates
+///
+/// Keyword operators are reported in upper case with single spaces, e.g. `NOT IN` or
+/// `IS NOT NULL`; the AND inside `BETWEEN ... AND ...` is not counted as a logical AND.
+///
+/// # Arguments
+/// * `input` - SQL query string to inspect
+///
+/// # Returns
+/// Map from operator to number of occurrences, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn operator_histogram(input: &str) -> Result<HashMap<String, usize>, pest::error::Error<Rule>> {
+    let pairs = parse_sql(input)?;
+    let mut histogram: HashMap<String, usize> = HashMap::new();
+
+    for pair in pairs.flatten() {
+        let (operator, count) = match pair.as_rule() {
+            Rule::or_expr => ("OR".to_string(), pair.into_inner().count() - 1),
+            Rule::and_expr => ("AND".to_string(), pair.into_inner().count() - 1),
+            Rule::not_expr => {
+                let start = pair.as_span().start();
+                let negated = pair
+                    .into_inner()
+                    .next()
+                    .is_some_and(|comparison| comparison.as_span().start() > start);
+                ("NOT".to_string(), usize::from(negated))
+            }
+            Rule::comparison_suffix => {
+                let start = pair.as_span().start();
+                // Keyword forms end before their first operand or IN's parenthesis
+                let operator = match pair.clone().into_inner().next() {
+                    Some(first) if first.as_rule() == Rule::comp_op => first.as_str(),
+                    Some(first) => &input[start..first.as_span().start()],
+                    None => pair.as_str(),
+                };
+                let operator = operator.split('(').next().unwrap_or_default();
+                (normalize_gap_tokens(operator), 1)
+            }
+            _ => continue,
+        };
+        if count > 0 {
+            *histogram.entry(operator).or_default() += count;
+        }
+    }
+
+    Ok(histogram)
+}
+
+/// Check whether the query sorts rows randomly (`ORDER BY RANDOM()`, `RAND()`, `NEWID()`)
+///
+/// Random ordering forces the database to sort the whole input, which is an expensive way to
+/// sample rows.
+///
+/// # Arguments
+/// * `input` - SQL query string to check
+///
+/// # Returns
+/// `true` when an ORDER BY item is a call to a random-generating function, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn has_random_order(input: &str) -> Result<bool, pest::error::Error<Rule>> {
+    let pairs = parse_sql(input)?;
+
+    Ok(pairs
+        .flatten()
+        .filter(|pair| pair.as_rule() == Rule::order_item)
+        .filter_map(|item| item.into_inner().next().and_then(bare_function_name))
+        .any(|name| {
+            let name = name.rsplit('.').next().unwrap_or_default().to_uppercase();
+            ["RANDOM", "RAND", "NEWID"].contains(&name.as_str())
+        }))
+}
+
+/// Infer the type of each result column of the outermost SELECT
+///
+/// Literals map to `integer`, `numeric`, `text`, or `boolean`; columns are looked up in `schema`
+/// after resolving table aliases (an unqualified column is looked up in every table the query
+/// reads and must have a single type); `::` casts yield their target type; `COUNT` yields
+/// `bigint`, `AVG` `numeric`, `MIN` and `MAX` their argument's type, and `SUM` the type
+/// PostgreSQL gives it. Anything else is `None`.
+///
+/// # Arguments
+/// * `input` - SQL query string to analyze
+/// * `schema` - Known column types
+///
+/// # Returns
+/// Result column name (or expression text when unnamed) and inferred type, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn infer_output_types(
+    input: &str,
+    schema: &Schema,
+) -> Result<Vec<(String, Option<String>)>, pest::error::Error<Rule>> {
+    let pairs = parse_sql(input)?;
+    let metadata = analyze_tree(pairs.clone());
+    let Some(select) = leading_select(pairs) else {
+        return Ok(Vec::new());
+    };
+
+    let items: Vec<_> = select
+        .clone()
+        .into_inner()
+        .filter(|pair| pair.as_rule() == Rule::projection)
+        .flat_map(|projection| projection.into_inner())
+        .flat_map(|list| list.into_inner())
+        .collect();
+    let types = select_output_columns(select)
+        .into_iter()
+        .enumerate()
+        .map(|(index, column)| {
+            let inferred = items
+                .get(index)
+                .and_then(|item| item.clone().into_inner().next())
+                .filter(|expr| expr.as_rule() == Rule::expr)
+                .and_then(|expr| infer_expr_type(expr, schema, &metadata));
+            (column.name.unwrap_or(column.expr), inferred)
+        })
+        .collect();
+
+    Ok(types)
+}
+
+/// Find EXISTS subqueries that project `*` or several columns
+///
+/// EXISTS only tests whether a row is produced, so `EXISTS (SELECT 1 FROM t ...)` states the
+/// intent without making the reader or the planner look at a column list.
+///
+/// # Arguments
+/// * `input` - SQL query string to check
+///
+/// # Returns
+/// Text of each flagged EXISTS predicate in order of appearance, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn exists_select_star(input: &str) -> Result<Vec<String>, pest::error::Error<Rule>> {
+    let pairs = parse_sql(input)?;
+
+    Ok(pairs
+        .flatten()
+        .filter(|pair| pair.as_rule() == Rule::exists_expr)
+        .filter(|exists| {
+            let columns = leading_select(exists.clone().into_inner())
+                .map(select_output_columns)
+                .unwrap_or_default();
+            columns.len() > 1 || columns.iter().any(|column| column.expr.ends_with('*'))
+        })
+        .map(|exists| exists.as_str().trim_end().to_string())
+        .collect())
+}
+
+/// Find tables read more than once by the same SELECT under the same name
+///
+/// `FROM orders, orders` or `orders JOIN orders ON ...` without distinct aliases is almost always
+/// a mistake; a self-join that gives each occurrence its own alias is not reported.
+///
+/// # Arguments
+/// * `input` - SQL query string to check
+///
+/// # Returns
+/// Each flagged table once, in order of appearance, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn duplicate_unaliased_tables(input: &str) -> Result<Vec<String>, pest::error::Error<Rule>> {
+    let pairs = parse_sql(input)?;
+    let mut duplicates = Vec::new();
+
+    for select in pairs
+        .flatten()
+        .filter(|pair| pair.as_rule() == Rule::select_stmt)
+    {
+        let mut references = HashSet::new();
+        let table_factors = select
+            .into_inner()
+            .filter(|pair| matches!(pair.as_rule(), Rule::from_item | Rule::join_clause))
+            .flat_map(|clause| clause.into_inner())
+            .filter(|pair| pair.as_rule() == Rule::table_factor);
+        for table_factor in table_factors {
+            let mut parts = table_factor.into_inner();
+            let Some(table) = parts
+                .next()
+                .filter(|part| part.as_rule() == Rule::qualified_identifier)
+            else {
+                continue;
+            };
+            let table = table.as_str().trim_end().to_string();
+            let name = parts
+                .find(|part| part.as_rule() == Rule::alias_identifier)
+                .map_or_else(
+                    || table.clone(),
+                    |alias| alias.as_str().trim_end().to_string(),
+                );
+
+            if !references.insert((table.clone(), name)) && !duplicates.contains(&table) {
+                duplicates.push(table);
+            }
+        }
+    }
+
+    Ok(duplicates)
+}
+
+/// Count the literal values of each `IN (...)` list
+///
+/// Long lists suggest poor selectivity or a value set better loaded into a temporary table.
+/// `IN` over a subquery is not reported.
+///
+/// # Arguments
+/// * `input` - SQL query string to analyze
+///
+/// # Returns
+/// Tested expression (the column, for `col IN (...)`) and literal count per predicate, in order of
+/// appearance, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn in_list_sizes(input: &str) -> Result<Vec<(String, usize)>, pest::error::Error<Rule>> {
+    let pairs = parse_sql(input)?;
+    let mut sizes = Vec::new();
+
+    for comparison in pairs
+        .flatten()
+        .filter(|pair| pair.as_rule() == Rule::comparison)
+    {
+        let mut parts = comparison.into_inner();
+        let Some(left) = parts.next() else {
+            continue;
+        };
+        let lists = parts
+            .filter_map(|suffix| {
+                suffix
+                    .into_inner()
+                    .find(|part| part.as_rule() == Rule::in_rhs)
+            })
+            .filter_map(|in_rhs| in_rhs.into_inner().next())
+            .filter(|list| list.as_rule() == Rule::expr_list);
+        for list in lists {
+            let count = list
+                .into_inner()
+                .filter(|value| bare_literal(value.clone()).is_some())
+                .count();
+            sizes.push((left.as_str().trim_end().to_string(), count));
+        }
+    }
+
+    Ok(sizes)
+}
+
+/// Find LIKE predicates whose pattern starts with a wildcard
+///
+/// A pattern such as `'%foo'` or `'_oo'` cannot use an ordinary B-tree index on the column.
+///
+/// # Arguments
+/// * `input` - SQL query string to check
+///
+/// # Returns
+/// Text of each flagged predicate in order of appearance, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn leading_wildcard_likes(input: &str) -> Result<Vec<String>, pest::error::Error<Rule>> {
+    let pairs = parse_sql(input)?;
+
+    Ok(pairs
+        .flatten()
+        .filter(|pair| pair.as_rule() == Rule::comparison)
+        .filter(|comparison| {
+            comparison
+                .clone()
+                .into_inner()
+                .filter(|part| part.as_rule() == Rule::comparison_suffix)
+                .any(is_leading_wildcard_like)
+        })
+        .map(|comparison| comparison.as_str().trim_end().to_string())
+        .collect())
+}
+
+/// Find comparisons whose right-hand side concatenates a literal with other values
+///
+/// A predicate such as `name = 'a' || x` usually means the SQL text was assembled from strings
+/// rather than bound parameters, which is worth a second look for injection risk.
+///
+/// # Arguments
+/// * `input` - SQL query string to check
+///
+/// # Returns
+/// Text of each flagged predicate in order of appearance, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn concatenated_literal_predicates(
+    input: &str,
+) -> Result<Vec<String>, pest::error::Error<Rule>> {
+    let pairs = parse_sql(input)?;
+    let mut flagged = Vec::new();
+
+    collect_concatenated_literal_predicates(pairs, &mut flagged);
+
+    Ok(flagged)
+}
+
+/// Find columns that feed the PARTITION BY or ORDER BY of a window function
+///
+/// These are the sort keys a pre-sorted or materialized input would need.
+///
+/// # Arguments
+/// * `input` - SQL query string to analyze
+///
+/// # Returns
+/// Column references as written, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn window_projection_columns(input: &str) -> Result<HashSet<String>, pest::error::Error<Rule>> {
+    let pairs = parse_sql(input)?;
+
+    Ok(pairs
+        .flatten()
+        .filter(|pair| pair.as_rule() == Rule::window_spec)
+        .flat_map(|spec| spec.into_inner().flatten())
+        .filter(|pair| pair.as_rule() == Rule::column)
+        .map(|column| column.as_str().to_string())
+        .collect())
+}
+
+/// Find columns used as operands of arithmetic operators (`+`, `-`, `*`, `/`, `%`)
+///
+/// Useful for lints such as spotting columns whose arithmetic might overflow. Operands of `||`
+/// concatenation are not arithmetic and are not reported.
+///
+/// # Arguments
+/// * `input` - SQL query string to analyze
+///
+/// # Returns
+/// Column references as written, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn columns_in_arithmetic(input: &str) -> Result<HashSet<String>, pest::error::Error<Rule>> {
+    let pairs = parse_sql(input)?;
+    let mut columns = HashSet::new();
+
+    collect_arithmetic_columns(pairs, &mut columns);
+
+    Ok(columns)
+}
+
+/// Score how likely the query's joins are to multiply rows
+///
+/// Each outer join (LEFT, RIGHT, FULL) adds 1 and each join without an equality between columns
+/// in its ON condition (including CROSS joins and range joins) adds 2.
+///
+/// # Arguments
+/// * `meta` - Metadata produced by one of the analysis functions
+///
+/// # Returns
+/// Fan-out risk score; 0 for queries with only inner equi-joins
+pub fn fanout_risk(meta: &QueryMetadata) -> u32 {
+    meta.joins
+        .iter()
+        .map(|join| {
+            let outer = join.join_type.as_deref().is_some_and(|join_type| {
+                ["LEFT", "RIGHT", "FULL"]
+                    .iter()
+                    .any(|outer| join_type.starts_with(outer))
+            });
+            u32::from(outer) + if join.on_columns.is_empty() { 2 } else { 0 }
+        })
+        .sum()
+}
+
+/// Check whether a query fetches one row of one table by a bound key
+///
+/// A SELECT on a single table whose WHERE compares a key-like column (`id` or `*_id`) with a
+/// bind parameter, and which either has `LIMIT 1` or computes no aggregates, is typically run
+/// once per row of an earlier result: the N+1 pattern ORMs produce.
+///
+/// # Arguments
+/// * `meta` - Metadata produced by one of the analysis functions
+///
+/// # Returns
+/// `true` when the query looks like a per-row lookup
+pub fn is_single_row_lookup(meta: &QueryMetadata) -> bool {
+    let keyed = meta.where_predicates.iter().any(|predicate| {
+        let column = predicate
+            .left
+            .rsplit('.')
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        let parameter = predicate
+            .right
+            .as_deref()
+            .is_some_and(|right| right.starts_with(['?', '$', ':', '@']));
+        predicate.operator == "=" && parameter && (column == "id" || column.ends_with("_id"))
+    });
+
+    meta.kind == QueryKind::Select
+        && meta.tables.len() == 1
+        && meta.joins.is_empty()
+        && keyed
+        && (meta.limit == Some(1) || meta.aggregates.is_empty())
+}
+
+/// Check whether running the query twice is guaranteed to give the same result without side
+/// effects, so its result may be cached
+///
+/// Any statement other than a SELECT is treated as a write. A SELECT is non-deterministic when it
+/// calls a function whose value depends on the clock, a random generator, a sequence, or the
+/// session (`NOW()`, `RANDOM()`, `CURRENT_TIMESTAMP`, `gen_random_uuid()`, `nextval(...)`, ...).
+///
+/// # Arguments
+/// * `meta` - Metadata produced by one of the analysis functions
+///
+/// # Returns
+/// `true` when the query is a SELECT free of non-deterministic functions
+pub fn is_deterministic(meta: &QueryMetadata) -> bool {
+    const NON_DETERMINISTIC: &[&str] = &[
+        "NOW",
+        "CURRENT_TIMESTAMP",
+        "CURRENT_TIME",
+        "CURRENT_DATE",
+        "CURRENT_USER",
+        "LOCALTIMESTAMP",
+        "LOCALTIME",
+        "CLOCK_TIMESTAMP",
+        "STATEMENT_TIMESTAMP",
+        "TRANSACTION_TIMESTAMP",
+        "TIMEOFDAY",
+        "SYSDATE",
+        "GETDATE",
+        "UNIX_TIMESTAMP",
+        "RANDOM",
+        "RAND",
+        "SETSEED",
+        "NEWID",
+        "UUID",
+        "GEN_RANDOM_UUID",
+        "UUID_GENERATE_V4",
+        "NEXTVAL",
+    ];
+
+    meta.kind == QueryKind::Select
+        && !meta.functions.iter().any(|function| {
+            let name = function.rsplit('.').next().unwrap_or_default().trim();
+            NON_DETERMINISTIC.contains(&name.to_uppercase().as_str())
+        })
+}
+
+/// Count how often each column is referenced across many analyzed queries
+///
+/// Keys are `table.column` with aliases resolved. An unqualified reference is attributed to the
+/// query's table when it reads exactly one, and counted under the bare column name otherwise.
+/// `table.*` projections are not counted.
+///
+/// # Arguments
+/// * `metas` - Metadata of the analyzed queries
+///
+/// # Returns
+/// Column -> number of references
+pub fn column_usage_counts(metas: &[QueryMetadata]) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+
+    for meta in metas {
+        let sole_table = match meta.tables.iter().collect::<Vec<_>>().as_slice() {
+            [table] if meta.joins.is_empty() => Some(table.as_str()),
+            _ => None,
+        };
+        for column in meta
+            .qualified_columns
+            .iter()
+            .filter(|column| column.column != "*")
+        {
+            let key = match column.table.as_deref().or(sole_table) {
+                Some(table) => format!("{table}.{}", column.column),
+                None => column.column.clone(),
+            };
+            *counts.entry(key).or_insert(0) += 1;
+        }
+    }
+
+    counts
+}
+
+/// Find aliases that reuse the name of a base table referenced by the query
+///
+/// An alias such as `customers AS orders` next to a real `orders` table confuses readers and
+/// tools resolving `orders.x`. Aliasing a table to its own name is not reported.
+///
+/// # Arguments
+/// * `meta` - Metadata produced by one of the analysis functions
+///
+/// # Returns
+/// Sorted alias names that collide with a table name
+pub fn shadowing_aliases(meta: &QueryMetadata) -> Vec<String> {
+    let mut shadowing: Vec<String> = meta
+        .aliases
+        .iter()
+        .filter(|(alias, table)| alias != table && meta.tables.contains(*alias))
+        .map(|(alias, _)| alias.clone())
+        .chain(
+            meta.derived_aliases
+                .iter()
+                .filter(|alias| meta.tables.contains(*alias))
+                .cloned(),
+        )
+        .collect();
+    shadowing.sort();
+
+    shadowing
+}
+
+/// Check whether the query references a table, using the dialect's identifier folding
+///
+/// Unquoted names are folded the way the dialect stores them (lower case for PostgreSQL, upper
+/// case for standard SQL, case-insensitive for SQLite and SQL Server, unchanged for MySQL).
+/// Quoted names keep their exact spelling.
+///
+/// # Arguments
+/// * `meta` - Metadata produced by one of the analysis functions
+/// * `name` - Table name to look for, quoted or unquoted
+/// * `dialect` - Dialect whose folding rules apply
+///
+/// # Returns
+/// `true` when one of the query's tables names the same table
+pub fn tables_match(meta: &QueryMetadata, name: &str, dialect: Dialect) -> bool {
+    let target = fold_identifier(name, dialect);
+    meta.tables.iter().any(|table| {
+        let folded: Vec<String> = table
+            .split('.')
+            .map(|part| match meta.quoted_identifiers.contains(part) {
+                true => part.to_string(),
+                false => fold_case(part, dialect),
+            })
+            .collect();
+        folded.join(".") == target
+    })
+}
+
+/// Check whether a query quotes identifiers in more than one style
+///
+/// Mixing `"col"`, `` `col` ``, and `[col]` usually means parts of the query were copied from
+/// different dialects.
+///
+/// # Arguments
+/// * `input` - SQL query string to analyze
+///
+/// # Returns
+/// `true` when more than one identifier-quoting style appears, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn mixed_quote_styles(input: &str) -> Result<bool, pest::error::Error<Rule>> {
+    let pairs = parse_sql(input)?;
+    let mut styles = HashSet::new();
+
+    collect_quote_styles(pairs, &mut styles);
+
+    Ok(styles.len() > 1)
+}
+
+/// Compute a canonical fingerprint of a query
+///
+/// Keywords are uppercased, whitespace and comments are collapsed, and literal `IN` lists are
+/// sorted and deduplicated, so `IN (3, 1, 2)` and `IN (1, 2, 3)` fingerprint identically.
+/// Literal values themselves are kept.
+///
+/// # Arguments
+/// * `input` - SQL query string to fingerprint
+///
+/// # Returns
+/// Canonical query text, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn fingerprint(input: &str) -> Result<String, pest::error::Error<Rule>> {
+    let pairs = parse_sql(input)?;
+
+    Ok(render_canonical(input, pairs, LiteralStyle::Keep))
+}
+
+/// Normalize a query to its shape, for grouping queries that differ only in their literals
+///
+/// The shape is the canonical text of [`sanitize_for_tracing`] with keywords lowercased: unlike
+/// [`fingerprint`], which keeps literal values and uppercases keywords, it replaces literals by
+/// `?` placeholders. Names, bind parameters, and the row counts of LIMIT, OFFSET, FETCH, and TOP
+/// are kept as written. The hash is the 64-bit FNV-1a hash of the shape, which stays the same
+/// across runs and builds.
+///
+/// # Arguments
+/// * `input` - SQL query string to normalize
+///
+/// # Returns
+/// NormalizedQuery with the shape text and its hash, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn normalize_sql(input: &str) -> Result<NormalizedQuery, pest::error::Error<Rule>> {
+    let pairs = parse_sql(input)?;
+    let mut tokens = canonical_tokens(
+        input,
+        pairs,
+        LiteralStyle::Placeholder(PlaceholderStyle::Question),
+    );
+    for (kind, text) in &mut tokens {
+        if *kind == TokenKind::Keyword {
+            *text = text.to_ascii_lowercase();
+        }
+    }
+
+    let shape = join_canonical_tokens(&tokens);
+    let hash = fnv1a_64(shape.as_bytes());
+    Ok(NormalizedQuery { shape, hash })
+}
+
+/// Render a query with every literal value replaced by a `?` placeholder
+///
+/// Uses the same canonical form as [`fingerprint`], so queries that differ only in their
+/// literal values sanitize to the same text.
+///
+/// # Arguments
+/// * `input` - SQL query string to sanitize
+///
+/// # Returns
+/// Canonical query text without literal values, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn sanitize_for_tracing(input: &str) -> Result<String, pest::error::Error<Rule>> {
+    sanitize_with(input, PlaceholderStyle::Question)
+}
+
+/// Render a query with every literal value replaced by a placeholder of the given style
+///
+/// Numbered placeholders count literals from left to right. `$N` numbering continues after the
+/// highest `$N` parameter the query already uses, so existing parameters keep their meaning.
+///
+/// # Arguments
+/// * `input` - SQL query string to sanitize
+/// * `placeholder_style` - Placeholder written for each literal
+///
+/// # Returns
+/// Canonical query text without literal values, or parsing error
+#[allow(clippy::result_large_err)]
+pub fn sanitize_with(
+    input: &str,
+    placeholder_style: PlaceholderStyle,
+) -> Result<String, pest::error::Error<Rule>> {
+    let pairs = parse_sql(input)?;
+
+    Ok(render_canonical(
+        input,
+        pairs,
+        LiteralStyle::Placeholder(placeholder_style),
+    ))
+}
+
+/// Parse a query and reject constructs not allowed by the given options
+#[allow(clippy::result_large_err)]
+fn parse_with_options<'i>(
+    input: &'i str,
+    options: &AnalyzeOptions,
+) -> Result<pest::iterators::Pairs<'i, Rule>, pest::error::Error<Rule>> {
+    let pairs = SqlParser::parse(Rule::sql, input)?;
+    validate_parsed(pairs.clone(), options)?;
+    Ok(pairs)
+}
+
+/// Reject constructs in a parse tree that are not allowed by the given options
+#[allow(clippy::result_large_err)]
+fn validate_parsed(
+    pairs: pest::iterators::Pairs<Rule>,
+    options: &AnalyzeOptions,
+) -> Result<(), pest::error::Error<Rule>> {
+    validate_dialect(pairs.clone(), options.dialect)?;
+    reject_chained_comparisons(pairs.clone())?;
+    if !options.lenient {
+        reject_empty_in_lists(pairs)?;
+    }
+    Ok(())
+}
+
+/// Reject comparison chains such as `1 < x < 10`, which SQL does not support
+#[allow(clippy::result_large_err)]
+fn reject_chained_comparisons(
+    pairs: pest::iterators::Pairs<Rule>,
+) -> Result<(), pest::error::Error<Rule>> {
+    for pair in pairs {
+        if let Rule::comparison = pair.as_rule() {
+            let suffixes = pair
+                .clone()
+                .into_inner()
+                .filter(|inner| inner.as_rule() == Rule::comparison_suffix)
+                .count();
+            if suffixes > 1 {
+                let span = pair.as_span();
+                let end = span.start() + span.as_str().trim_end().len();
+                return Err(pest::error::Error::new_from_span(
+                    pest::error::ErrorVariant::CustomError {
+                        message: "chained comparison not allowed; use BETWEEN or AND".to_string(),
+                    },
+                    pest::Span::new(span.get_input(), span.start(), end).unwrap_or(span),
+                ));
+            }
+        }
+
+        reject_chained_comparisons(pair.into_inner())?;
+    }
+    Ok(())
+}
+
+/// Reject `IN ()` lists, which standard SQL does not allow
+#[allow(clippy::result_large_err)]
+fn reject_empty_in_lists(
+    pairs: pest::iterators::Pairs<Rule>,
+) -> Result<(), pest::error::Error<Rule>> {
+    for pair in pairs {
+        if is_empty_in_list(&pair) {
+            let span = pair.as_span();
+            let text = span.as_str().trim_end();
+            let paren = span.start() + text.find('(').unwrap_or(0);
+            return Err(pest::error::Error::new_from_span(
+                pest::error::ErrorVariant::CustomError {
+                    message: "empty IN list is not allowed".to_string(),
+                },
+                pest::Span::new(span.get_input(), paren, span.start() + text.len()).unwrap_or(span),
+            ));
+        }
+
+        reject_empty_in_lists(pair.into_inner())?;
+    }
+    Ok(())
+}
+
+/// Collect the predicate text of every comparison using an empty IN list
+fn collect_empty_in_lists(pairs: pest::iterators::Pairs<Rule>, predicates: &mut Vec<String>) {
+    for pair in pairs {
+        if let Rule::comparison = pair.as_rule() {
+            if pair
+                .clone()
+                .into_inner()
+                .any(|suffix| is_empty_in_list(&suffix))
+            {
+                predicates.push(pair.as_str().trim_end().to_string());
+            }
+        }
+        collect_empty_in_lists(pair.into_inner(), predicates);
+    }
+}
+
+/// Check whether a pair is an `IN ()` comparison suffix without any values
+fn is_empty_in_list(pair: &pest::iterators::Pair<Rule>) -> bool {
+    pair.as_rule() == Rule::comparison_suffix
+        && pair.clone().into_inner().next().is_none()
+        && pair.as_str().trim_end().ends_with(')')
+}
+
+/// Report a grammar failure as [`ParseError::Unsupported`] when parsing stopped at a keyword that
+/// starts a construct the grammar does not support; any other error stays a syntax error
+fn classify_syntax_error(input: &str, error: pest::error::Error<Rule>) -> ParseError {
+    const UNSUPPORTED: &[(&str, Option<&str>, &str)] = &[
+        ("RETURNING", None, "RETURNING clauses"),
+        ("FOR", Some("UPDATE"), "row locking clauses (FOR UPDATE)"),
+        ("FOR", Some("SHARE"), "row locking clauses (FOR SHARE)"),
+        ("ON", Some("CONFLICT"), "ON CONFLICT clauses"),
+        ("ON", Some("DUPLICATE"), "ON DUPLICATE KEY UPDATE clauses"),
+        ("MERGE", None, "MERGE statements"),
+        ("WINDOW", None, "named windows (WINDOW)"),
+        ("PIVOT", None, "PIVOT"),
+        ("UNPIVOT", None, "UNPIVOT"),
+    ];
+
+    let position = match error.location {
+        pest::error::InputLocation::Pos(position) => position,
+        pest::error::InputLocation::Span((start, _)) => start,
+    };
+    if !matches!(
+        error.variant,
+        pest::error::ErrorVariant::ParsingError { .. }
+    ) {
+        return ParseError::Syntax(error);
+    }
+
+    let rest = input.get(position..).unwrap_or_default();
+    let start = position + (rest.len() - rest.trim_start().len());
+    let mut words = rest
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .filter(|word| !word.is_empty());
+    let (Some(first), second) = (words.next(), words.next()) else {
+        return ParseError::Syntax(error);
+    };
+    let (first, second) = (
+        first.to_ascii_uppercase(),
+        second.map(str::to_ascii_uppercase),
+    );
+    if !rest.trim_start().to_ascii_uppercase().starts_with(&first) {
+        return ParseError::Syntax(error);
+    }
+
+    match UNSUPPORTED.iter().find(|(keyword, next, _)| {
+        *keyword == first && (next.is_none() || second.as_deref() == *next)
+    }) {
+        Some((_, _, feature)) => ParseError::Unsupported {
+            feature: feature.to_string(),
+            span: start..start + first.len(),
+        },
+        None => ParseError::Syntax(error),
+    }
+}
+
+/// Reject syntax that the grammar accepts but the chosen dialect does not support
+#[allow(clippy::result_large_err)]
+fn validate_dialect(
+    pairs: pest::iterators::Pairs<Rule>,
+    dialect: Dialect,
+) -> Result<(), pest::error::Error<Rule>> {
+    for pair in pairs {
+        let unsupported = match pair.as_rule() {
+            Rule::index_hint if dialect != Dialect::MySql => Some("index hints"),
+            Rule::insert_or if dialect != Dialect::Sqlite => Some("INSERT OR conflict clauses"),
+            Rule::regex_op if dialect != Dialect::Postgres => Some("regex match operators"),
+            Rule::null_safe_eq if !matches!(dialect, Dialect::Generic | Dialect::MySql) => {
+                Some("`<=>` comparisons")
+            }
+            Rule::sort_operator if dialect != Dialect::Postgres => {
+                Some("ORDER BY ... USING operators")
+            }
+            Rule::top_clause if dialect != Dialect::MsSql => Some("TOP clauses"),
+            Rule::limit_clause if dialect == Dialect::MsSql => Some("LIMIT clauses"),
+            Rule::cast_suffix if !matches!(dialect, Dialect::Generic | Dialect::Postgres) => {
+                Some("`::` casts")
+            }
+            Rule::identifier
+            | Rule::qualified_identifier
+            | Rule::alias
+            | Rule::alias_identifier
+            | Rule::column
+            | Rule::qualified_wildcard => {
+                let mut styles = HashSet::new();
+                identifier_quote_styles(pair.as_str(), &mut styles);
+                styles
+                    .iter()
+                    .find(|style| !dialect.identifier_quotes().contains(style))
+                    .map(|style| match style {
+                        '`' => "backtick-quoted identifiers",
+                        '[' => "bracket-quoted identifiers",
+                        _ => "double-quoted identifiers",
+                    })
+            }
+            _ => None,
+        };
+
+        if let Some(feature) = unsupported {
+            return Err(pest::error::Error::new_from_span(
+                pest::error::ErrorVariant::CustomError {
+                    message: format!("{} are not supported by the {:?} dialect", feature, dialect),
+                },
+                pair.as_span(),
+            ));
+        }
+
+        validate_dialect(pair.into_inner(), dialect)?;
+    }
+    Ok(())
+}
+
+/// Append one line per node of the subtree rooted at `pair`, indented by its depth
+fn write_tree_node(pair: pest::iterators::Pair<Rule>, depth: usize, out: &mut String) {
+    let span = pair.as_span();
+    let line = format!(
+        "{}{:?} [{}..{}]",
+        "  ".repeat(depth),
+        pair.as_rule(),
+        span.start(),
+        span.end()
+    );
+    let mut children = pair.clone().into_inner().peekable();
+    if children.peek().is_none() {
+        out.push_str(&format!("{} {:?}\n", line, pair.as_str()));
+    } else {
+        out.push_str(&line);
+        out.push('\n');
+        for child in children {
+            write_tree_node(child, depth + 1, out);
+        }
+    }
+}
+
+/// Analyze a whole parse tree, then resolve references that depend on the complete alias map
+fn analyze_tree(pairs: pest::iterators::Pairs<Rule>) -> QueryMetadata {
+    let mut metadata = QueryMetadata::default();
+    analyze_tree_into(pairs, &mut metadata, MetadataFields::all());
+    metadata
+}
+
+/// Add the metadata of a parse tree to `metadata`, resolving aliases only in the columns it adds
+fn analyze_tree_into(
+    pairs: pest::iterators::Pairs<Rule>,
+    metadata: &mut QueryMetadata,
+    fields: MetadataFields,
+) {
+    if let Some(first) = pairs.clone().next() {
+        let span = first.as_span();
+        let end = pairs
+            .clone()
+            .last()
+            .map_or(span.end(), |last| last.as_span().end());
+        metadata
+            .hints
+            .extend(optimizer_hints(&span.get_input()[span.start()..end]));
+    }
+
+    let before = NamesBefore::record(metadata);
+    let first_added = before.qualified_columns;
+    metadata.kind = QueryKind::default();
+    if fields.contains(MetadataFields::LITERALS) {
+        collect_parameters(pairs.clone(), &mut 0, &mut metadata.parameters);
+    }
+    analyze_pairs(pairs, metadata, fields);
+
+    let resolved = resolve_columns(metadata, &metadata.qualified_columns[first_added..]);
+    metadata.resolved_columns.extend(resolved);
+    for column in &mut metadata.qualified_columns[first_added..] {
+        if let Some(table) = column.table.as_mut() {
+            if let Some(resolved) = metadata.aliases.get(table.as_str()) {
+                *table = resolved.clone();
+            }
+        }
+    }
+
+    unquote_metadata(metadata, before);
+}
+
+/// Resolve column references to base tables, using the complete alias map
+fn resolve_columns(metadata: &QueryMetadata, columns: &[QualifiedColumn]) -> Vec<ColumnRef> {
+    let base_tables: HashSet<&str> = metadata
+        .tables
+        .iter()
+        .chain(metadata.joins.iter().map(|join| &join.table))
+        .map(String::as_str)
+        .filter(|table| !metadata.derived_aliases.contains(*table) && !is_cte_name(metadata, table))
+        .collect();
+    let sole_table = match base_tables.iter().collect::<Vec<_>>().as_slice() {
+        [table] => Some(table.to_string()),
+        _ => None,
+    };
+
+    columns
+        .iter()
+        .filter(|column| column.column != "*")
+        .map(|column| {
+            let table = match column.table.as_deref() {
+                Some(qualifier) => {
+                    let table = metadata
+                        .aliases
+                        .get(qualifier)
+                        .map_or(qualifier, String::as_str);
+                    base_tables.contains(table).then(|| table.to_string())
+                }
+                None => sole_table.clone(),
+            };
+            ColumnRef {
+                name: column.column.clone(),
+                qualifier: column.table.clone(),
+                table,
+            }
+        })
+        .collect()
+}
+
+/// Names and list lengths of metadata before a statement is analyzed, so that only the names the
+/// statement adds are unquoted
+struct NamesBefore {
+    tables: HashSet<String>,
+    schemas: HashSet<String>,
+    columns: HashSet<String>,
+    cte_names: HashSet<String>,
+    derived_aliases: HashSet<String>,
+    indexes: HashSet<String>,
+    aliases: HashMap<String, String>,
+    derived_tables: HashMap<String, HashSet<String>>,
+    joins: usize,
+    index_hints: usize,
+    qualified_columns: usize,
+    resolved_columns: usize,
+    cursor: Option<String>,
+    view: Option<ViewInfo>,
+    table_definition: Option<TableDefinition>,
+}
+
+impl NamesBefore {
+    /// Record the names already in `metadata`, taking out the single-valued fields so that a
+    /// statement which sets them can be told apart from one which does not
+    fn record(metadata: &mut QueryMetadata) -> Self {
+        NamesBefore {
+            tables: metadata.tables.clone(),
+            schemas: metadata.schemas.clone(),
+            columns: metadata.columns.clone(),
+            cte_names: metadata.cte_names.clone(),
+            derived_aliases: metadata.derived_aliases.clone(),
+            indexes: metadata.indexes.clone(),
+            aliases: metadata.aliases.clone(),
+            derived_tables: metadata.derived_tables.clone(),
+            joins: metadata.joins.len(),
+            index_hints: metadata.index_hints.len(),
+            qualified_columns: metadata.qualified_columns.len(),
+            resolved_columns: metadata.resolved_columns.len(),
+            cursor: metadata.cursor.take(),
+            view: metadata.view.take(),
+            table_definition: metadata.table_definition.take(),
+        }
+    }
+}
+
+/// Replace the quoted table, alias, column, and index names added since `before` with their
+/// unquoted canonical form, remembering which names were quoted
+///
+/// Names recorded earlier are already canonical and are left alone, so that analyzing several
+/// queries into one metadata struct does not unquote a name twice.
+fn unquote_metadata(metadata: &mut QueryMetadata, before: NamesBefore) {
+    let mut quoted = std::mem::take(&mut metadata.quoted_identifiers);
+    let quoted = &mut quoted;
+
+    unquote_set(&mut metadata.tables, &before.tables, quoted);
+    unquote_set(&mut metadata.schemas, &before.schemas, quoted);
+    unquote_set(&mut metadata.columns, &before.columns, quoted);
+    unquote_set(&mut metadata.cte_names, &before.cte_names, quoted);
+    unquote_set(
+        &mut metadata.derived_aliases,
+        &before.derived_aliases,
+        quoted,
+    );
+    unquote_set(&mut metadata.indexes, &before.indexes, quoted);
+    metadata.aliases = metadata
+        .aliases
+        .drain()
+        .map(|(alias, table)| {
+            if before.aliases.get(&alias) == Some(&table) {
+                (alias, table)
+            } else {
+                (
+                    canonical_name(&alias, quoted),
+                    canonical_name(&table, quoted),
+                )
+            }
+        })
+        .collect();
+    metadata.derived_tables = metadata
+        .derived_tables
+        .drain()
+        .map(
+            |(alias, mut reads)| match before.derived_tables.get(&alias) {
+                Some(earlier) => {
+                    unquote_set(&mut reads, earlier, quoted);
+                    (alias, reads)
+                }
+                None => {
+                    unquote_set(&mut reads, &HashSet::new(), quoted);
+                    (canonical_name(&alias, quoted), reads)
+                }
+            },
+        )
+        .collect();
+
+    for join in &mut metadata.joins[before.joins..] {
+        unquote_in_place(&mut join.table, quoted);
+        if let Some(alias) = join.alias.as_mut() {
+            unquote_in_place(alias, quoted);
+        }
+        for (left, right) in &mut join.on_columns {
+            unquote_in_place(left, quoted);
+            unquote_in_place(right, quoted);
+        }
+    }
+    for (table, _) in &mut metadata.index_hints[before.index_hints..] {
+        unquote_in_place(table, quoted);
+    }
+    for column in &mut metadata.qualified_columns[before.qualified_columns..] {
+        if let Some(table) = column.table.as_mut() {
+            unquote_in_place(table, quoted);
+        }
+        unquote_in_place(&mut column.column, quoted);
+    }
+    for column in &mut metadata.resolved_columns[before.resolved_columns..] {
+        unquote_in_place(&mut column.name, quoted);
+        for name in [&mut column.qualifier, &mut column.table]
+            .into_iter()
+            .flatten()
+        {
+            unquote_in_place(name, quoted);
+        }
+    }
+    match metadata.cursor.as_mut() {
+        Some(cursor) => unquote_in_place(cursor, quoted),
+        None => metadata.cursor = before.cursor,
+    }
+    match metadata.view.as_mut() {
+        Some(view) => {
+            unquote_in_place(&mut view.name, quoted);
+            view.columns
+                .iter_mut()
+                .for_each(|column| unquote_in_place(column, quoted));
+            unquote_set(&mut view.source_tables, &HashSet::new(), quoted);
+        }
+        None => metadata.view = before.view,
+    }
+    match metadata.table_definition.as_mut() {
+        Some(table) => {
+            unquote_in_place(&mut table.name, quoted);
+            let key_columns = table
+                .columns
+                .iter_mut()
+                .map(|column| &mut column.name)
+                .chain(table.primary_key.iter_mut())
+                .chain(table.unique.iter_mut().flatten());
+            key_columns.for_each(|column| unquote_in_place(column, quoted));
+            for key in &mut table.foreign_keys {
+                unquote_in_place(&mut key.referenced_table, quoted);
+                key.columns
+                    .iter_mut()
+                    .chain(key.referenced_columns.iter_mut())
+                    .for_each(|column| unquote_in_place(column, quoted));
+            }
+        }
+        None => metadata.table_definition = before.table_definition,
+    }
+
+    metadata.quoted_identifiers = std::mem::take(quoted);
+}
+
+/// Replace a name with its canonical form
+fn unquote_in_place(name: &mut String, quoted: &mut HashSet<String>) {
+    *name = canonical_name(name, quoted);
+}
+
+/// Replace every name of a set that is not in `before` with its canonical form
+fn unquote_set(
+    names: &mut HashSet<String>,
+    before: &HashSet<String>,
+    quoted: &mut HashSet<String>,
+) {
+    *names = names
+        .drain()
+        .map(|name| {
+            if before.contains(&name) {
+                name
+            } else {
+                canonical_name(&name, quoted)
+            }
+        })
+        .collect();
+}
+
+/// Extract the bodies of `/*+ ... */` comments, skipping string literals, quoted identifiers,
+/// and ordinary comments
+fn optimizer_hints(text: &str) -> Vec<String> {
+    let mut hints = Vec::new();
+    let mut rest = text;
+
+    let skip_to = |rest: &str, offset: usize, terminator: &str| {
+        rest[offset..]
+            .find(terminator)
+            .map_or(rest.len(), |end| offset + end + terminator.len())
+    };
+
+    while let Some(ch) = rest.chars().next() {
+        let consumed = if rest.starts_with("/*+") {
+            let end = rest.find("*/").unwrap_or(rest.len());
+            hints.push(rest[3..end].trim().to_string());
+            skip_to(rest, 3, "*/")
+        } else if rest.starts_with("/*") {
+            skip_to(rest, 2, "*/")
+        } else if rest.starts_with("--") {
+            skip_to(rest, 2, "\n")
+        } else if let Some(close) = match ch {
+            '\'' => Some("'"),
+            '"' => Some("\""),
+            '`' => Some("`"),
+            _ => None,
+        } {
+            skip_to(rest, 1, close)
+        } else {
+            ch.len_utf8()
+        };
+
+        rest = &rest[consumed..];
+    }
+
+    hints
+}
+
+/// Recursively analyze parse tree pairs and extract metadata
+fn analyze_pairs(
+    pairs: pest::iterators::Pairs<Rule>,
+    metadata: &mut QueryMetadata,
+    fields: MetadataFields,
+) {
+    for pair in pairs {
+        match pair.as_rule() {
+            Rule::statement => analyze_pairs(pair.into_inner(), metadata, fields),
+            Rule::select_stmt => analyze_select_stmt(pair.into_inner(), metadata, fields),
+            Rule::insert_stmt => {
+                metadata.kind = QueryKind::Insert;
+                analyze_insert_stmt(pair.into_inner(), metadata, fields)
+            }
+            Rule::update_stmt => {
+                metadata.kind = QueryKind::Update;
+                analyze_update_stmt(pair.into_inner(), metadata, fields)
+            }
+            Rule::delete_stmt => {
+                metadata.kind = QueryKind::Delete;
+                analyze_delete_stmt(pair.into_inner(), metadata, fields)
+            }
+            Rule::create_table_stmt => {
+                metadata.kind = QueryKind::CreateTable;
+                analyze_create_table_stmt(pair.into_inner(), metadata)
+            }
+            Rule::create_view_stmt => {
+                metadata.kind = QueryKind::CreateView;
+                analyze_create_view_stmt(pair.into_inner(), metadata, fields)
+            }
+            Rule::alter_table_stmt => {
+                metadata.kind = QueryKind::AlterTable;
+                analyze_schema_change(pair.into_inner(), metadata, fields)
+            }
+            Rule::drop_table_stmt => {
+                metadata.kind = QueryKind::DropTable;
+                analyze_schema_change(pair.into_inner(), metadata, fields)
+            }
+            Rule::create_index_stmt => {
+                metadata.kind = QueryKind::CreateIndex;
+                analyze_schema_change(pair.into_inner(), metadata, fields)
+            }
+            Rule::drop_index_stmt => {
+                metadata.kind = QueryKind::DropIndex;
+                analyze_schema_change(pair.into_inner(), metadata, fields)
+            }
+            Rule::set_session_stmt => metadata.kind = QueryKind::Set,
+            Rule::tx_stmt => metadata.kind = QueryKind::Transaction,
+            Rule::with_clause => analyze_with_clause(pair.into_inner(), metadata, fields),
+            _ => analyze_pairs(pair.into_inner(), metadata, fields),
+        }
+    }
+}
+
+/// Analyze common table expressions, registering each name so later references to it are not
+/// reported as base tables
+///
+/// A recursive CTE is in scope inside its own body; a plain CTE only after it.
+fn analyze_with_clause(
+    pairs: pest::iterators::Pairs<Rule>,
+    metadata: &mut QueryMetadata,
+    fields: MetadataFields,
+) {
+    let recursive = pairs.clone().any(|pair| pair.as_rule() == Rule::recursive);
+
+    for cte in pairs.filter(|pair| pair.as_rule() == Rule::cte) {
+        let mut inner = cte.into_inner();
+        let Some(name) = inner.next() else {
+            continue;
+        };
+        let name = name.as_str().to_string();
+
+        if recursive {
+            metadata.cte_names.insert(name.clone());
+        }
+        analyze_pairs(inner, metadata, fields);
+        metadata.cte_names.insert(name);
+    }
+}
+
+/// Check whether a table reference names a common table expression; unquoted names compare
+/// case-insensitively
+fn is_cte_name(metadata: &QueryMetadata, name: &str) -> bool {
+    metadata
+        .cte_names
+        .iter()
+        .any(|cte| cte.eq_ignore_ascii_case(name))
+}
+
+/// Analyze SELECT statement components
+fn analyze_select_stmt(
+    pairs: pest::iterators::Pairs<Rule>,
+    metadata: &mut QueryMetadata,
+    fields: MetadataFields,
+) {
+    let mut limit = None;
+
+    for pair in pairs {
+        match pair.as_rule() {
+            Rule::limit_clause | Rule::fetch_clause | Rule::top_clause => {
+                // A FETCH without a count returns one row; a bind parameter leaves it unknown
+                limit = pair
+                    .into_inner()
+                    .find(|part| matches!(part.as_rule(), Rule::number | Rule::parameter))
+                    .map_or(Some(1), |count| count.as_str().trim_end().parse().ok());
+            }
+            Rule::from_item => analyze_from_item(pair.into_inner(), metadata, fields),
+            Rule::join_clause => analyze_join_clause(pair.into_inner(), metadata, fields),
+            Rule::projection => analyze_projection(pair.into_inner(), metadata, fields),
+            Rule::where_clause => analyze_where_clause(pair.into_inner(), metadata, fields),
+            Rule::group_by_clause => analyze_group_by_clause(pair.into_inner(), metadata, fields),
+            Rule::having_clause => analyze_having_clause(pair.into_inner(), metadata, fields),
+            Rule::order_by_clause => analyze_order_by_clause(pair.into_inner(), metadata, fields),
+            _ => analyze_pairs(pair.into_inner(), metadata, fields),
+        }
+    }
+
+    // Nested SELECTs are analyzed first, so the outermost one assigns last
+    metadata.limit = limit;
+}
+
+/// Analyze GROUP BY expressions
+fn analyze_group_by_clause(
+    pairs: pest::iterators::Pairs<Rule>,
+    metadata: &mut QueryMetadata,
+    fields: MetadataFields,
+) {
+    for expr in pairs.flat_map(|list| list.into_inner()) {
+        if fields.contains(MetadataFields::COLUMNS) {
+            metadata.group_by.push(expr.as_str().trim_end().to_string());
+        }
+        analyze_expression_for_metadata(expr.into_inner(), metadata, fields);
+    }
+}
+
+/// Analyze ORDER BY items
+fn analyze_order_by_clause(
+    pairs: pest::iterators::Pairs<Rule>,
+    metadata: &mut QueryMetadata,
+    fields: MetadataFields,
+) {
+    for item in pairs.flat_map(|list| list.into_inner()) {
+        let mut order_item = OrderByItem {
+            expr: String::new(),
+            direction: None,
+            using_operator: None,
+        };
+
+        for pair in item.into_inner() {
+            match pair.as_rule() {
+                Rule::expr => {
+                    order_item.expr = pair.as_str().trim_end().to_string();
+                    analyze_expression_for_metadata(pair.into_inner(), metadata, fields);
+                }
+                Rule::sort_direction => {
+                    order_item.direction = Some(pair.as_str().trim_end().to_uppercase());
+                }
+                Rule::sort_operator => {
+                    order_item.using_operator = Some(pair.as_str().to_string());
+                }
+                _ => {}
+            }
+        }
+
+        if fields.contains(MetadataFields::COLUMNS) {
+            metadata.order_by.push(order_item);
+        }
+    }
+}
+
+/// Analyze FROM clause items
+fn analyze_from_item(
+    pairs: pest::iterators::Pairs<Rule>,
+    metadata: &mut QueryMetadata,
+    fields: MetadataFields,
+) {
+    for pair in pairs {
+        if let Rule::table_factor = pair.as_rule() {
+            analyze_table_factor(pair.into_inner(), metadata, fields);
+        }
+    }
+}
+
+/// Analyze table references and their aliases
+fn analyze_table_factor(
+    pairs: pest::iterators::Pairs<Rule>,
+    metadata: &mut QueryMetadata,
+    fields: MetadataFields,
+) {
+    let mut table_name = None;
+    let mut alias = None;
+    let mut hints = Vec::new();
+    let mut derived_reads = None;
+
+    for pair in pairs {
+        match pair.as_rule() {
+            Rule::qualified_identifier => {
+                table_name = Some(pair.as_str().to_string());
+            }
+            Rule::alias_identifier => {
+                alias = Some(pair.as_str().to_string());
+            }
+            Rule::index_hint => hints.push(pair.as_str().to_string()),
+            Rule::compound_select => {
+                let mut reads = HashSet::new();
+                collect_table_reads(pair.clone().into_inner(), &mut reads);
+                derived_reads = Some(reads);
+                analyze_pairs(pair.into_inner(), metadata, fields);
+            }
+            _ => analyze_pairs(pair.into_inner(), metadata, fields),
+        }
+    }
+
+    if let (Some(reads), Some(alias_name)) = (derived_reads, alias.clone()) {
+        metadata.derived_aliases.insert(alias_name.clone());
+        metadata.derived_tables.insert(alias_name, reads);
+    }
+
+    if let Some(table) = table_name {
+        if !is_cte_name(metadata, &table) {
+            insert_table(metadata, &table);
+        }
+        for hint in hints {
+            metadata.index_hints.push((table.clone(), hint));
+        }
+        if let Some(alias_name) = alias {
+            metadata.aliases.insert(alias_name, table);
+        }
+    }
+}
+
+/// Record a table under its full name, along with the schema it is qualified by
+fn insert_table(metadata: &mut QueryMetadata, table: &str) {
+    insert_schema(metadata, table);
+    metadata.tables.insert(table.to_string());
+}
+
+/// Record the `schema` or `database.schema` prefix of a qualified table name
+fn insert_schema(metadata: &mut QueryMetadata, table: &str) {
+    if let Some((schema, _)) = table.rsplit_once('.') {
+        metadata.schemas.insert(schema.to_string());
+    }
+}
+
+/// Collect the base tables read anywhere inside a subquery, including every set-op branch
+fn collect_table_reads(pairs: pest::iterators::Pairs<Rule>, reads: &mut HashSet<String>) {
+    for pair in pairs {
+        if pair.as_rule() == Rule::table_factor {
+            let mut inner = pair.clone().into_inner();
+            if let Some(first) = inner.next() {
+                if first.as_rule() == Rule::qualified_identifier {
+                    reads.insert(first.as_str().trim_end().to_string());
+                }
+            }
+        }
+        collect_table_reads(pair.into_inner(), reads);
+    }
+}
+
+/// Byte range of the first table that is comma-joined onto a FROM clause
+fn find_implicit_join(pairs: pest::iterators::Pairs<Rule>) -> Option<std::ops::Range<usize>> {
+    for pair in pairs {
+        if pair.as_rule() == Rule::from_item {
+            if let Some(joined) = pair.clone().into_inner().nth(1) {
+                let span = joined.as_span();
+                return Some(span.start()..span.start() + joined.as_str().trim_end().len());
+            }
+        }
+        if let Some(span) = find_implicit_join(pair.into_inner()) {
+            return Some(span);
+        }
+    }
+
+    None
+}
+
+/// Analyze JOIN clauses and extract join information
+fn analyze_join_clause(
+    pairs: pest::iterators::Pairs<Rule>,
+    metadata: &mut QueryMetadata,
+    fields: MetadataFields,
+) {
+    let mut join_type = None;
+    let mut table = None;
+    let mut alias = None;
+    let mut hints = Vec::new();
+    let mut derived_reads = None;
+    let mut condition = String::new();
+    let mut on_columns = Vec::new();
+    let mut equi_join = false;
+
+    for pair in pairs {
+        match pair.as_rule() {
+            Rule::JOIN_TYPE => {
+                // Tokenizing drops comments written between the keywords
+                join_type = Some(normalize_gap_tokens(pair.as_str()))
+            }
+            Rule::table_factor => {
+                for inner_pair in pair.into_inner() {
+                    match inner_pair.as_rule() {
+                        Rule::qualified_identifier => {
+                            table = Some(inner_pair.as_str().to_string());
+                        }
+                        Rule::alias_identifier => {
+                            alias = Some(inner_pair.as_str().to_string());
+                        }
+                        Rule::index_hint => hints.push(inner_pair.as_str().to_string()),
+                        Rule::compound_select => {
+                            let mut reads = HashSet::new();
+                            collect_table_reads(inner_pair.clone().into_inner(), &mut reads);
+                            derived_reads = Some(reads);
+                            analyze_pairs(inner_pair.into_inner(), metadata, fields);
+                        }
+                        _ => analyze_pairs(inner_pair.into_inner(), metadata, fields),
+                    }
+                }
+            }
+            Rule::ON_KEY => {}
+            _ => {
+                condition = pair.as_str().to_string();
+                equi_join = is_equi_condition(pair.clone());
+                collect_column_equalities(pair.clone().into_inner(), &mut on_columns);
+                analyze_expression_for_metadata(pair.into_inner(), metadata, fields);
+            }
+        }
+    }
+
+    if let (Some(reads), Some(alias_name)) = (derived_reads, alias.clone()) {
+        metadata.derived_aliases.insert(alias_name.clone());
+        metadata.derived_tables.insert(alias_name, reads);
+    }
+
+    if let Some(table_name) = table {
+        insert_schema(metadata, &table_name);
+        for hint in hints {
+            metadata.index_hints.push((table_name.clone(), hint));
+        }
+        if let Some(alias_name) = alias.clone() {
+            metadata.aliases.insert(alias_name, table_name.clone());
+        }
+
+        metadata.joins.push(JoinInfo {
+            join_type,
+            table: table_name,
+            alias,
+            normalized_condition: condition.split_whitespace().collect::<Vec<_>>().join(" "),
+            condition,
+            on_columns,
+            equi_join,
+        });
+    }
+}
+
+/// Whether every top-level conjunct of a join condition is an `=` comparison
+fn is_equi_condition(expr: pest::iterators::Pair<Rule>) -> bool {
+    let Some(or_expr) = expr.into_inner().next() else {
+        return false;
+    };
+    let mut and_exprs = or_expr.into_inner();
+    let (Some(and_expr), None) = (and_exprs.next(), and_exprs.next()) else {
+        return false;
+    };
+
+    and_expr.into_inner().all(|not_expr| {
+        let Some(comparison) = not_expr.clone().into_inner().next() else {
+            return false;
+        };
+        if comparison.as_str().trim_end() != not_expr.as_str().trim_end() {
+            return false;
+        }
+        let mut parts = comparison.into_inner();
+        let (Some(_), Some(suffix), None) = (parts.next(), parts.next(), parts.next()) else {
+            return false;
+        };
+        suffix.into_inner().next().is_some_and(|operator| {
+            operator.as_rule() == Rule::comp_op && operator.as_str().trim_end() == "="
+        })
+    })
+}
+
+/// Collect column pairs compared with `=` in a condition, leaving nested subqueries out
+fn collect_column_equalities(
+    pairs: pest::iterators::Pairs<Rule>,
+    equalities: &mut Vec<(String, String)>,
+) {
+    for pair in pairs {
+        match pair.as_rule() {
+            Rule::compound_select => {}
+            Rule::comparison => equalities.extend(column_equality(pair)),
+            _ => collect_column_equalities(pair.into_inner(), equalities),
+        }
+    }
+}
+
+/// Both sides of a comparison of the form `column = column`
+fn column_equality(comparison: pest::iterators::Pair<Rule>) -> Option<(String, String)> {
+    let mut parts = comparison.into_inner();
+    let (Some(left), Some(suffix), None) = (parts.next(), parts.next(), parts.next()) else {
+        return None;
+    };
+    let mut suffix_parts = suffix.into_inner();
+    let (Some(operator), Some(right)) = (suffix_parts.next(), suffix_parts.next()) else {
+        return None;
+    };
+    if operator.as_rule() != Rule::comp_op || operator.as_str().trim_end() != "=" {
+        return None;
+    }
+
+    Some((bare_column(left)?, bare_column(right)?))
+}
+
+/// Analyze SELECT projection (column list or *)
+fn analyze_projection(
+    pairs: pest::iterators::Pairs<Rule>,
+    metadata: &mut QueryMetadata,
+    fields: MetadataFields,
+) {
+    for pair in pairs {
+        match pair.as_rule() {
+            Rule::projection_list => {
+                for item in pair.into_inner() {
+                    if let Rule::projection_item = item.as_rule() {
+                        analyze_projection_item(item.into_inner(), metadata, fields);
+                    }
+                }
+            }
+            _ => analyze_pairs(pair.into_inner(), metadata, fields),
+        }
+    }
+}
+
+/// Analyze individual projection items (columns, expressions)
+fn analyze_projection_item(
+    pairs: pest::iterators::Pairs<Rule>,
+    metadata: &mut QueryMetadata,
+    fields: MetadataFields,
+) {
+    for pair in pairs {
+        match pair.as_rule() {
+            Rule::expr => analyze_expression_for_metadata(pair.into_inner(), metadata, fields),
+            Rule::qualified_wildcard => {
+                if fields.contains(MetadataFields::COLUMNS) {
+                    let table = pair.as_str().trim_end_matches(".*");
+                    metadata.qualified_columns.push(QualifiedColumn {
+                        table: Some(table.to_string()),
+                        column: "*".to_string(),
+                    });
+                }
+            }
+            _ => analyze_pairs(pair.into_inner(), metadata, fields),
+        }
+    }
+}
+
+/// Analyze WHERE clause expressions
+fn analyze_where_clause(
+    pairs: pest::iterators::Pairs<Rule>,
+    metadata: &mut QueryMetadata,
+    fields: MetadataFields,
+) {
+    for pair in pairs {
+        if let Rule::expr = pair.as_rule() {
+            if fields.contains(MetadataFields::PREDICATES) {
+                collect_predicates(pair.clone().into_inner(), &mut metadata.where_predicates);
+            }
+            analyze_expression_for_metadata(pair.into_inner(), metadata, fields);
+        }
+    }
+}
+
+/// Analyze HAVING clause expressions
+fn analyze_having_clause(
+    pairs: pest::iterators::Pairs<Rule>,
+    metadata: &mut QueryMetadata,
+    fields: MetadataFields,
+) {
+    for pair in pairs {
+        if let Rule::expr = pair.as_rule() {
+            if fields.contains(MetadataFields::PREDICATES) {
+                collect_predicates(pair.clone().into_inner(), &mut metadata.having_predicates);
+            }
+            analyze_expression_for_metadata(pair.into_inner(), metadata, fields);
+        }
+    }
+}
+
+/// Collect comparisons from a condition, leaving nested subqueries to their own analysis
+fn collect_predicates(pairs: pest::iterators::Pairs<Rule>, predicates: &mut Vec<Predicate>) {
+    for pair in pairs {
+        match pair.as_rule() {
+            Rule::compound_select => {}
+            Rule::comparison => {
+                let mut inner = pair.clone().into_inner();
+                if let Some(left) = inner.next() {
+                    let left = left.as_str().trim_end();
+                    for suffix in inner {
+                        predicates.push(predicate_from_suffix(left, suffix));
+                    }
+                }
+                collect_predicates(pair.into_inner(), predicates);
+            }
+            _ => collect_predicates(pair.into_inner(), predicates),
+        }
+    }
+}
+
+/// Collect comparisons whose right-hand operand is a `||` chain involving a literal
+fn collect_concatenated_literal_predicates(
+    pairs: pest::iterators::Pairs<Rule>,
+    flagged: &mut Vec<String>,
+) {
+    for pair in pairs {
+        if let Rule::comparison = pair.as_rule() {
+            let concatenated = pair
+                .clone()
+                .into_inner()
+                .filter(|inner| inner.as_rule() == Rule::comparison_suffix)
+                .filter_map(|suffix| suffix.into_inner().nth(1))
+                .any(|operand| is_concatenation_with_literal(&operand));
+            if concatenated {
+                flagged.push(pair.as_str().trim_end().to_string());
+            }
+        }
+
+        collect_concatenated_literal_predicates(pair.into_inner(), flagged);
+    }
+}
+
+/// Check whether an `addition` joins operands with `||` and at least one operand is a literal
+fn is_concatenation_with_literal(operand: &pest::iterators::Pair<Rule>) -> bool {
+    if operand.as_rule() != Rule::addition {
+        return false;
+    }
+
+    let parts: Vec<_> = operand.clone().into_inner().collect();
+    parts.iter().any(|part| part.as_rule() == Rule::concat_op)
+        && parts
+            .iter()
+            .filter(|part| part.as_rule() == Rule::multiplication)
+            .any(|part| bare_literal(part.clone()).is_some())
+}
+
+/// Collect table, alias, and column symbols, resolving qualifiers through the query's aliases
+fn collect_symbols(
+    pairs: pest::iterators::Pairs<Rule>,
+    metadata: &QueryMetadata,
+    symbols: &mut Vec<Symbol>,
+) {
+    // Metadata holds canonical names, so quoted references are unquoted before the lookup
+    let canonical = |name: &str| canonical_name(name, &mut HashSet::new());
+    let resolve = |name: &str| {
+        let name = canonical(name);
+        metadata
+            .aliases
+            .get(&name)
+            .cloned()
+            .or_else(|| metadata.tables.contains(&name).then_some(name))
+    };
+
+    for pair in pairs {
+        let span = pair.as_span();
+        match pair.as_rule() {
+            Rule::table_factor => {
+                for part in pair.clone().into_inner() {
+                    let part_span = part.as_span();
+                    let (kind, table) = match part.as_rule() {
+                        Rule::qualified_identifier => {
+                            (SymbolKind::Table, Some(canonical(part.as_str())))
+                        }
+                        Rule::alias_identifier => (SymbolKind::TableAlias, resolve(part.as_str())),
+                        _ => continue,
+                    };
+                    symbols.push(Symbol {
+                        name: part.as_str().to_string(),
+                        kind,
+                        table,
+                        span: part_span.start()..part_span.end(),
+                    });
+                }
+                collect_symbols(pair.into_inner(), metadata, symbols);
+            }
+            Rule::column => {
+                let text = pair.as_str();
+                match text.rsplit_once('.') {
+                    Some((qualifier, column)) => {
+                        let table = resolve(qualifier);
+                        let kind = if metadata.aliases.contains_key(&canonical(qualifier)) {
+                            SymbolKind::TableAlias
+                        } else {
+                            SymbolKind::Table
+                        };
+                        symbols.push(Symbol {
+                            name: qualifier.to_string(),
+                            kind,
+                            table: table.clone(),
+                            span: span.start()..span.start() + qualifier.len(),
+                        });
+                        symbols.push(Symbol {
+                            name: column.to_string(),
+                            kind: SymbolKind::Column,
+                            table,
+                            span: span.end() - column.len()..span.end(),
+                        });
+                    }
+                    None => symbols.push(Symbol {
+                        name: text.to_string(),
+                        kind: SymbolKind::Column,
+                        table: None,
+                        span: span.start()..span.end(),
+                    }),
+                }
+            }
+            _ => collect_symbols(pair.into_inner(), metadata, symbols),
+        }
+    }
+}
+
+/// Collect bare column operands next to an arithmetic operator
+fn collect_arithmetic_columns(pairs: pest::iterators::Pairs<Rule>, columns: &mut HashSet<String>) {
+    for pair in pairs {
+        if let Rule::addition | Rule::multiplication = pair.as_rule() {
+            let parts: Vec<_> = pair.clone().into_inner().collect();
+            let is_arithmetic_neighbor = |index: Option<usize>| {
+                index
+                    .and_then(|index| parts.get(index))
+                    .is_some_and(|part| part.as_rule() != Rule::concat_op)
+            };
+
+            for (index, part) in parts.iter().enumerate() {
+                if part.as_rule() == Rule::concat_op {
+                    continue;
+                }
+                if is_arithmetic_neighbor(index.checked_sub(1))
+                    || is_arithmetic_neighbor(Some(index + 1))
+                {
+                    columns.extend(bare_column(part.clone()));
+                }
+            }
+        }
+
+        collect_arithmetic_columns(pair.into_inner(), columns);
+    }
+}
+
+/// Check whether a comparison suffix is `[NOT] LIKE` with a pattern literal starting with `%` or `_`
+fn is_leading_wildcard_like(suffix: pest::iterators::Pair<Rule>) -> bool {
+    let mut parts = suffix.into_inner();
+    let (Some(operator), Some(pattern)) = (parts.next(), parts.next()) else {
+        return false;
+    };
+    if operator.as_rule() != Rule::comp_op
+        || !operator
+            .as_str()
+            .trim_end()
+            .to_uppercase()
+            .ends_with("LIKE")
+    {
+        return false;
+    }
+
+    bare_literal(pattern)
+        .is_some_and(|pattern| pattern.starts_with("'%") || pattern.starts_with("'_"))
+}
+
+/// Column compared by an OR branch of the form `column = value`
+fn equality_column(branch: pest::iterators::Pair<Rule>) -> Option<String> {
+    let text = branch.as_str().trim_end();
+    let comparison = branch
+        .into_inner()
+        .flatten()
+        .find(|pair| pair.as_rule() == Rule::comparison)?;
+    if comparison.as_str().trim_end() != text {
+        return None;
+    }
+
+    let mut parts = comparison.into_inner();
+    let (Some(left), Some(suffix), None) = (parts.next(), parts.next(), parts.next()) else {
+        return None;
+    };
+    let operator = suffix.into_inner().next()?;
+    if operator.as_rule() != Rule::comp_op || operator.as_str().trim_end() != "=" {
+        return None;
+    }
+
+    bare_column(left)
+}
+
+/// Split a comparison suffix into its operator and right-hand operand
+fn predicate_from_suffix(left: &str, suffix: pest::iterators::Pair<Rule>) -> Predicate {
+    let text = suffix.as_str().trim_end();
+    let start = suffix.as_span().start();
+    let operand_start = suffix
+        .clone()
+        .into_inner()
+        .find(|part| part.as_rule() != Rule::comp_op)
+        .map_or(text.len(), |operand| operand.as_span().start() - start);
+
+    let mut operator = text[..operand_start].trim_end();
+    let mut right = &text[operand_start..];
+    if let Some(paren) = operator.find('(') {
+        right = &text[paren..];
+        operator = operator[..paren].trim_end();
+    }
+
+    Predicate {
+        left: left.to_string(),
+        operator: operator
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_uppercase(),
+        right: (!right.is_empty()).then(|| right.to_string()),
+    }
+}
+
+/// Extract metadata from expressions (columns, functions, tables)
+fn analyze_expression_for_metadata(
+    pairs: pest::iterators::Pairs<Rule>,
+    metadata: &mut QueryMetadata,
+    fields: MetadataFields,
+) {
+    for pair in pairs {
+        match pair.as_rule() {
+            Rule::column if !fields.contains(MetadataFields::COLUMNS) => {}
+            Rule::column => {
+                let column = pair.as_str();
+                metadata.columns.insert(column.to_string());
+                metadata
+                    .qualified_columns
+                    .push(match column.rsplit_once('.') {
+                        Some((table, name)) => QualifiedColumn {
+                            table: Some(table.to_string()),
+                            column: name.to_string(),
+                        },
+                        None => QualifiedColumn {
+                            table: None,
+                            column: column.to_string(),
+                        },
+                    });
+            }
+            Rule::literal => {
+                if fields.contains(MetadataFields::LITERALS) {
+                    metadata.literals.push(pair.as_str().trim_end().to_string());
+                }
+            }
+            Rule::function_call => {
+                let func_name = pair.as_str().split('(').next().unwrap_or("").to_string();
+                // Arguments are still walked for columns, literals and subquery tables
+                let is_aggregate =
+                    fields.contains(MetadataFields::FUNCTIONS) && is_aggregate_function(&func_name);
+                if fields.contains(MetadataFields::FUNCTIONS) {
+                    metadata.functions.insert(func_name.clone());
+                }
+                if is_aggregate {
+                    metadata.aggregates.insert(func_name.clone());
+                }
+
+                for inner_pair in pair.into_inner() {
+                    match inner_pair.as_rule() {
+                        Rule::distinct if is_aggregate => {
+                            metadata.distinct_aggregates.insert(func_name.clone());
+                        }
+                        Rule::expr => {
+                            analyze_expression_for_metadata(
+                                inner_pair.into_inner(),
+                                metadata,
+                                fields,
+                            );
+                        }
+                        Rule::filter_clause => {
+                            if is_aggregate {
+                                metadata.filtered_aggregates.insert(func_name.clone());
+                            }
+                            analyze_expression_for_metadata(
+                                inner_pair.into_inner(),
+                                metadata,
+                                fields,
+                            );
+                        }
+                        Rule::window_spec => {
+                            if is_aggregate {
+                                metadata.windowed_aggregates.insert(func_name.clone());
+                            }
+                            analyze_window_spec(func_name.clone(), inner_pair, metadata, fields);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            Rule::niladic_function | Rule::type_name
+                if !fields.contains(MetadataFields::FUNCTIONS) => {}
+            Rule::niladic_function => {
+                metadata.functions.insert(pair.as_str().to_string());
+            }
+            Rule::type_name => {
+                metadata.casts.push(pair.as_str().trim_end().to_string());
+            }
+            // EXISTS, IN and scalar subqueries get a full SELECT analysis, so their joins,
+            // aliases and predicates are recorded like those of the outer query; their LIMIT
+            // does not describe the statement
+            Rule::compound_select => {
+                let limit = metadata.limit;
+                analyze_pairs(pair.into_inner(), metadata, fields);
+                metadata.limit = limit;
+            }
+            Rule::identifier | Rule::qualified_identifier => {
+                if !metadata.aliases.contains_key(pair.as_str())
+                    && !is_cte_name(metadata, pair.as_str())
+                {
+                    insert_table(metadata, pair.as_str());
+                }
+            }
+            _ => analyze_expression_for_metadata(pair.into_inner(), metadata, fields),
+        }
+    }
+}
+
+/// Record a window function call and the columns of its OVER clause
+fn analyze_window_spec(
+    function: String,
+    spec: pest::iterators::Pair<Rule>,
+    metadata: &mut QueryMetadata,
+    fields: MetadataFields,
+) {
+    let mut window = WindowFunction {
+        function,
+        partition_by: Vec::new(),
+        order_by: Vec::new(),
+        frame: None,
+    };
+
+    for clause in spec.into_inner() {
+        let items = match clause.as_rule() {
+            Rule::partition_by_clause => &mut window.partition_by,
+            Rule::order_by_clause => &mut window.order_by,
+            Rule::window_frame => {
+                window.frame = Some(normalize_gap_tokens(clause.as_str()));
+                continue;
+            }
+            _ => continue,
+        };
+        for item in clause.into_inner().flat_map(|list| list.into_inner()) {
+            items.push(item.as_str().trim_end().to_string());
+            analyze_expression_for_metadata(item.into_inner(), metadata, fields);
+        }
+    }
+
+    if fields.contains(MetadataFields::FUNCTIONS) {
+        metadata.window_functions.push(window);
+    }
+}
+
+/// Whether a function name is one of the standard aggregates
+fn is_aggregate_function(name: &str) -> bool {
+    let name = name.rsplit('.').next().unwrap_or(name);
+    ["SUM", "COUNT", "AVG", "MIN", "MAX"].contains(&name.to_uppercase().as_str())
+}
+
+/// Analyze INSERT statements
+fn analyze_insert_stmt(
+    pairs: pest::iterators::Pairs<Rule>,
+    metadata: &mut QueryMetadata,
+    fields: MetadataFields,
+) {
+    for pair in pairs {
+        match pair.as_rule() {
+            Rule::qualified_identifier => insert_table(metadata, pair.as_str()),
+            Rule::insert_or => {
+                if let Some(action) = pair.into_inner().next() {
+                    let action = action.as_str().trim_end().to_uppercase();
+                    metadata.is_upsert = action == "REPLACE";
+                    metadata.insert_or_action = Some(action);
+                }
+            }
+            Rule::expr => {
+                analyze_expression_for_metadata(pair.into_inner(), metadata, fields);
+            }
+            _ => analyze_pairs(pair.into_inner(), metadata, fields),
+        }
+    }
+}
+
+/// Analyze CREATE TABLE statements
+fn analyze_create_table_stmt(pairs: pest::iterators::Pairs<Rule>, metadata: &mut QueryMetadata) {
+    for pair in pairs.clone() {
+        match pair.as_rule() {
+            Rule::qualified_identifier => insert_table(metadata, pair.as_str()),
+            Rule::table_option => {
+                metadata
+                    .table_options
+                    .push(pair.as_str().trim_end().to_string());
+            }
+            _ => {}
+        }
+    }
+
+    metadata.table_definition = Some(table_definition(pairs));
+}
+
+/// Analyze ALTER TABLE, DROP TABLE, CREATE INDEX and DROP INDEX statements, recording the
+/// affected tables, columns and indexes
+fn analyze_schema_change(
+    pairs: pest::iterators::Pairs<Rule>,
+    metadata: &mut QueryMetadata,
+    fields: MetadataFields,
+) {
+    for pair in pairs {
+        match pair.as_rule() {
+            // Tables are qualified names, so a bare identifier can only name an index
+            Rule::identifier => {
+                metadata
+                    .indexes
+                    .insert(pair.as_str().trim_end().to_string());
+            }
+            Rule::qualified_identifier => insert_table(metadata, pair.as_str().trim_end()),
+            _ if !fields.contains(MetadataFields::COLUMNS) => {}
+            Rule::column_list => metadata.columns.extend(column_list_names(pair)),
+            Rule::alter_add_column | Rule::alter_drop_column | Rule::alter_rename_column => {
+                for part in pair.into_inner() {
+                    let column = match part.as_rule() {
+                        Rule::column_definition => part.into_inner().next(),
+                        Rule::identifier => Some(part),
+                        _ => None,
+                    };
+                    if let Some(column) = column {
+                        metadata
+                            .columns
+                            .insert(column.as_str().trim_end().to_string());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Build the structured definition of a CREATE TABLE statement
+fn table_definition(pairs: pest::iterators::Pairs<Rule>) -> TableDefinition {
+    let mut table = TableDefinition::default();
+
+    for pair in pairs {
+        match pair.as_rule() {
+            Rule::qualified_identifier => table.name = pair.as_str().trim_end().to_string(),
+            Rule::column_definition => {
+                let mut inner = pair.into_inner();
+                let (Some(name), Some(data_type)) = (inner.next(), inner.next()) else {
+                    continue;
+                };
+                let mut column = ColumnDefinition {
+                    name: name.as_str().trim_end().to_string(),
+                    data_type: data_type.as_str().trim_end().to_string(),
+                    nullable: true,
+                    default: None,
+                };
+
+                for constraint in inner {
+                    let keywords = normalize_gap_tokens(constraint.as_str());
+                    match constraint.into_inner().next() {
+                        Some(references) if references.as_rule() == Rule::references_clause => {
+                            table
+                                .foreign_keys
+                                .push(foreign_key(vec![column.name.clone()], references));
+                        }
+                        Some(value) => column.default = Some(value.as_str().trim_end().to_string()),
+                        None if keywords == "NOT NULL" => column.nullable = false,
+                        None if keywords == "PRIMARY KEY" => {
+                            column.nullable = false;
+                            table.primary_key.push(column.name.clone());
+                        }
+                        None if keywords == "UNIQUE" => {
+                            table.unique.push(vec![column.name.clone()])
+                        }
+                        None => {}
+                    }
+                }
+                table.columns.push(column);
+            }
+            Rule::table_constraint => {
+                let keywords = normalize_gap_tokens(pair.as_str());
+                let mut parts = pair
+                    .into_inner()
+                    .filter(|part| part.as_rule() != Rule::identifier);
+                let columns = parts.next().map(column_list_names).unwrap_or_default();
+
+                if let Some(references) = parts.next() {
+                    table.foreign_keys.push(foreign_key(columns, references));
+                } else if keywords.contains("PRIMARY KEY") {
+                    table.primary_key = columns;
+                } else {
+                    table.unique.push(columns);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Table-level primary keys make their columns NOT NULL as well
+    for column in &mut table.columns {
+        if table.primary_key.contains(&column.name) {
+            column.nullable = false;
+        }
+    }
+
+    table
+}
+
+fn foreign_key(columns: Vec<String>, references: pest::iterators::Pair<Rule>) -> ForeignKey {
+    let mut inner = references.into_inner();
+    ForeignKey {
+        columns,
+        referenced_table: inner
+            .next()
+            .map(|table| table.as_str().trim_end().to_string())
+            .unwrap_or_default(),
+        referenced_columns: inner.next().map(column_list_names).unwrap_or_default(),
+    }
+}
+
+fn column_list_names(column_list: pest::iterators::Pair<Rule>) -> Vec<String> {
+    column_list
+        .into_inner()
+        .flat_map(|list| list.into_inner())
+        .map(|identifier| identifier.as_str().trim_end().to_string())
+        .collect()
+}
+
+/// Analyze CREATE VIEW statements; the view's query is analyzed like any SELECT
+fn analyze_create_view_stmt(
+    pairs: pest::iterators::Pairs<Rule>,
+    metadata: &mut QueryMetadata,
+    fields: MetadataFields,
+) {
+    let mut view = ViewInfo {
+        name: String::new(),
+        columns: Vec::new(),
+        check_option: None,
+        materialized: false,
+        source_tables: HashSet::new(),
+    };
+
+    for pair in pairs {
+        match pair.as_rule() {
+            Rule::qualified_identifier => view.name = pair.as_str().to_string(),
+            Rule::materialized => view.materialized = true,
+            Rule::column_list => view.columns = column_list_names(pair),
+            Rule::check_option => {
+                view.check_option = Some(pair.into_inner().next().map_or_else(
+                    || "CASCADED".to_string(),
+                    |level| level.as_str().trim_end().to_uppercase(),
+                ));
+            }
+            _ => {
+                collect_table_reads(pair.clone().into_inner(), &mut view.source_tables);
+                analyze_pairs(pair.into_inner(), metadata, fields);
+            }
+        }
+    }
+
+    // CTE names are only known once the defining query has been analyzed
+    view.source_tables
+        .retain(|table| !is_cte_name(metadata, table));
+    metadata.view = Some(view);
+}
+
+/// Analyze UPDATE statements
+fn analyze_update_stmt(
+    pairs: pest::iterators::Pairs<Rule>,
+    metadata: &mut QueryMetadata,
+    fields: MetadataFields,
+) {
+    for pair in pairs {
+        match pair.as_rule() {
+            Rule::qualified_identifier => insert_table(metadata, pair.as_str()),
+            Rule::set_list => {
+                analyze_set_list(pair.into_inner(), metadata, fields);
+            }
+            Rule::where_clause => {
+                analyze_where_clause(pair.into_inner(), metadata, fields);
+            }
+            Rule::where_current_of => analyze_where_current_of(pair.into_inner(), metadata),
+            _ => analyze_pairs(pair.into_inner(), metadata, fields),
+        }
+    }
+}
+
+/// Analyze DELETE statements
+fn analyze_delete_stmt(
+    pairs: pest::iterators::Pairs<Rule>,
+    metadata: &mut QueryMetadata,
+    fields: MetadataFields,
+) {
+    for pair in pairs {
+        match pair.as_rule() {
+            Rule::qualified_identifier => insert_table(metadata, pair.as_str()),
+            Rule::where_clause => {
+                analyze_where_clause(pair.into_inner(), metadata, fields);
+            }
+            Rule::where_current_of => analyze_where_current_of(pair.into_inner(), metadata),
+            _ => analyze_pairs(pair.into_inner(), metadata, fields),
+        }
+    }
+}
+
+/// Record the cursor of a `WHERE CURRENT OF` clause
+fn analyze_where_current_of(pairs: pest::iterators::Pairs<Rule>, metadata: &mut QueryMetadata) {
+    for pair in pairs {
+        if let Rule::identifier = pair.as_rule() {
+            metadata.cursor = Some(pair.as_str().to_string());
+        }
+    }
+}
+
+/// Analyze SET clause in UPDATE statements
+fn analyze_set_list(
+    pairs: pest::iterators::Pairs<Rule>,
+    metadata: &mut QueryMetadata,
+    fields: MetadataFields,
+) {
+    for pair in pairs {
+        if let Rule::set_item = pair.as_rule() {
+            analyze_set_item(pair.into_inner(), metadata, fields);
+        }
+    }
+}
+
+/// Analyze individual SET items (column = value)
+fn analyze_set_item(
+    pairs: pest::iterators::Pairs<Rule>,
+    metadata: &mut QueryMetadata,
+    fields: MetadataFields,
+) {
+    for pair in pairs {
+        match pair.as_rule() {
+            Rule::identifier => {
+                if fields.contains(MetadataFields::COLUMNS) {
+                    metadata.columns.insert(pair.as_str().to_string());
+                }
+            }
+            Rule::expr => {
+                analyze_expression_for_metadata(pair.into_inner(), metadata, fields);
+            }
+            _ => analyze_pairs(pair.into_inner(), metadata, fields),
+        }
+    }
+}
+
+/// Record every bind parameter placeholder in the parse tree
+fn collect_bind_params(pairs: pest::iterators::Pairs<Rule>, summary: &mut BindSummary) {
+    for pair in pairs {
+        match pair.as_rule() {
+            Rule::anonymous_parameter => summary.anonymous_count += 1,
+            Rule::positional_parameter => {
+                let index = pair.as_str()[1..].parse().unwrap_or(usize::MAX);
+                summary.max_positional = summary.max_positional.max(index);
+            }
+            Rule::named_parameter => {
+                summary.named.insert(pair.as_str()[1..].to_string());
+            }
+            _ => collect_bind_params(pair.into_inner(), summary),
+        }
+    }
+}
+
+/// Record every bind parameter placeholder in the parse tree, numbering `?`s from `anonymous`
+fn collect_parameters(
+    pairs: pest::iterators::Pairs<Rule>,
+    anonymous: &mut usize,
+    parameters: &mut Vec<Parameter>,
+) {
+    for pair in pairs {
+        let text = pair.as_str();
+        let (style, name, index) = match pair.as_rule() {
+            Rule::anonymous_parameter => {
+                *anonymous += 1;
+                (ParameterStyle::Question, None, Some(*anonymous))
+            }
+            Rule::positional_parameter => (ParameterStyle::Dollar, None, text[1..].parse().ok()),
+            Rule::named_parameter if text.starts_with(':') => {
+                (ParameterStyle::Colon, Some(text[1..].to_string()), None)
+            }
+            Rule::named_parameter => (ParameterStyle::At, Some(text[1..].to_string()), None),
+            _ => {
+                collect_parameters(pair.into_inner(), anonymous, parameters);
+                continue;
+            }
+        };
+
+        let span = pair.as_span();
+        parameters.push(Parameter {
+            style,
+            name,
+            index,
+            span: (span.start(), span.end()),
+        });
+    }
+}
+
+/// Collect nested SELECTs whose ORDER BY is not accompanied by a row-limiting clause
+fn collect_pointless_orders(
+    pairs: pest::iterators::Pairs<Rule>,
+    nested: bool,
+    subqueries: &mut Vec<String>,
+) {
+    for pair in pairs {
+        match pair.as_rule() {
+            Rule::compound_select => {
+                if nested && has_pointless_order(pair.clone().into_inner()) {
+                    subqueries.push(pair.as_str().trim_end().to_string());
+                }
+                collect_pointless_orders(pair.into_inner(), true, subqueries);
+            }
+            _ => collect_pointless_orders(pair.into_inner(), nested, subqueries),
+        }
+    }
+}
+
+/// Check the SELECT branches of a compound query for ORDER BY without LIMIT/OFFSET/FETCH
+fn has_pointless_order(pairs: pest::iterators::Pairs<Rule>) -> bool {
+    pairs.into_iter().any(|pair| match pair.as_rule() {
+        Rule::union_clause | Rule::intersect_select | Rule::intersect_clause => {
+            has_pointless_order(pair.into_inner())
+        }
+        Rule::select_stmt => {
+            let clauses: Vec<Rule> = pair.into_inner().map(|clause| clause.as_rule()).collect();
+            clauses.contains(&Rule::order_by_clause)
+                && !clauses.iter().any(|clause| {
+                    matches!(
+                        clause,
+                        Rule::limit_clause
+                            | Rule::offset_clause
+                            | Rule::fetch_clause
+                            | Rule::top_clause
+                    )
+                })
+        }
+        _ => false,
+    })
+}
+
+/// Build the tree of a UNION/EXCEPT operand (a SELECT or an INTERSECT chain)
+fn set_operand_tree(operand: pest::iterators::Pair<Rule>) -> SetOpTree {
+    if operand.as_rule() != Rule::intersect_select {
+        return SetOpTree::Select(operand.as_str().trim_end().to_string());
+    }
+
+    let mut tree = None;
+    for part in operand.into_inner() {
+        tree = match tree {
+            Some(left) => Some(set_operation(left, part)),
+            None => Some(set_operand_tree(part)),
+        };
+    }
+    tree.unwrap_or_else(|| SetOpTree::Select(String::new()))
+}
+
+/// Combine a left operand with a UNION/EXCEPT/INTERSECT clause
+fn set_operation(left: SetOpTree, clause: pest::iterators::Pair<Rule>) -> SetOpTree {
+    let start = clause.as_span().start();
+    let text = clause.as_str();
+    let Some(operand) = clause.into_inner().next() else {
+        return left;
+    };
+
+    let operator = text[..operand.as_span().start() - start]
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_uppercase();
+
+    SetOpTree::Operation {
+        operator,
+        left: Box::new(left),
+        right: Box::new(set_operand_tree(operand)),
+    }
+}
+
+/// Check a SELECT for DISTINCT over a single ungrouped, unwindowed aggregate
+fn has_redundant_distinct(select: pest::iterators::Pair<Rule>) -> bool {
+    select
+        .clone()
+        .into_inner()
+        .any(|clause| clause.as_rule() == Rule::distinct)
+        && sole_aggregate(select).is_some()
+}
+
+/// Name of the aggregate when a SELECT without GROUP BY projects nothing but one unwindowed
+/// aggregate call
+fn sole_aggregate(select: pest::iterators::Pair<Rule>) -> Option<String> {
+    let clauses: Vec<_> = select.into_inner().collect();
+    if clauses
+        .iter()
+        .any(|clause| clause.as_rule() == Rule::group_by_clause)
+    {
+        return None;
+    }
 
-    analyze_pairs(pairs, &mut metadata);
+    let list = clauses
+        .into_iter()
+        .find(|clause| clause.as_rule() == Rule::projection)
+        .and_then(|projection| projection.into_inner().next())?;
+    let items: Vec<_> = list.into_inner().collect();
+    let [item] = items.as_slice() else {
+        return None;
+    };
 
-    Ok(metadata)
+    let windowed = item
+        .clone()
+        .into_inner()
+        .flatten()
+        .any(|pair| pair.as_rule() == Rule::window_spec);
+    item.clone()
+        .into_inner()
+        .next()
+        .and_then(bare_function_name)
+        .filter(|name| is_aggregate_function(name) && !windowed)
 }
 
-/// Analyze SQL query and return metadata as pretty-printed JSON
-///
-/// # Arguments
-/// * `input` - SQL query string to analyze
-///
-/// # Returns
-/// JSON string with query metadata, or parsing/serialization error
-#[allow(clippy::result_large_err)]
-pub fn analyze_sql_json(input: &str) -> Result<String, pest::error::Error<Rule>> {
-    let metadata = analyze_sql(input)?;
-    let json = serde_json::to_string_pretty(&metadata).map_err(|e| {
-        pest::error::Error::new_from_span(
-            pest::error::ErrorVariant::CustomError {
-                message: format!("JSON serialization error: {}", e),
-            },
-            pest::Span::new(input, 0, input.len()).unwrap(),
-        )
-    })?;
-    Ok(json)
+/// First SELECT branch of the outermost query, skipping its WITH clause
+fn leading_select(pairs: pest::iterators::Pairs<Rule>) -> Option<pest::iterators::Pair<Rule>> {
+    let operand = pairs
+        .flatten()
+        .find(|pair| pair.as_rule() == Rule::compound_select)?
+        .into_inner()
+        .find(|pair| matches!(pair.as_rule(), Rule::select_stmt | Rule::intersect_select))?;
+
+    match operand.as_rule() {
+        Rule::intersect_select => operand.into_inner().next(),
+        _ => Some(operand),
+    }
 }
 
-/// Recursively analyze parse tree pairs and extract metadata
-fn analyze_pairs(pairs: pest::iterators::Pairs<Rule>, metadata: &mut QueryMetadata) {
+/// Walk nested SELECT statements keeping a stack of the table names visible in each scope
+fn collect_correlations(
+    pairs: pest::iterators::Pairs<Rule>,
+    scopes: &mut Vec<HashSet<String>>,
+    correlations: &mut Vec<(String, String)>,
+) {
     for pair in pairs {
-        match pair.as_rule() {
-            Rule::statement => analyze_pairs(pair.into_inner(), metadata),
-            Rule::select_stmt => analyze_select_stmt(pair.into_inner(), metadata),
-            Rule::insert_stmt => analyze_insert_stmt(pair.into_inner(), metadata),
-            Rule::update_stmt => analyze_update_stmt(pair.into_inner(), metadata),
-            Rule::delete_stmt => analyze_delete_stmt(pair.into_inner(), metadata),
-            _ => analyze_pairs(pair.into_inner(), metadata),
+        if let Rule::select_stmt = pair.as_rule() {
+            let scope = select_scope(pair.clone().into_inner());
+            if !scopes.is_empty() {
+                for inner in pair.clone().into_inner() {
+                    if let Rule::where_clause = inner.as_rule() {
+                        find_correlated_equalities(
+                            inner.into_inner(),
+                            &scope,
+                            scopes,
+                            correlations,
+                        );
+                    }
+                }
+            }
+
+            scopes.push(scope);
+            collect_correlations(pair.into_inner(), scopes, correlations);
+            scopes.pop();
+        } else {
+            collect_correlations(pair.into_inner(), scopes, correlations);
         }
     }
 }
 
-/// Analyze SELECT statement components
-fn analyze_select_stmt(pairs: pest::iterators::Pairs<Rule>, metadata: &mut QueryMetadata) {
+/// Collect table names and aliases introduced by a SELECT's FROM and JOIN clauses
+fn select_scope(pairs: pest::iterators::Pairs<Rule>) -> HashSet<String> {
+    let mut scope = HashSet::new();
+
     for pair in pairs {
         match pair.as_rule() {
-            Rule::from_item => analyze_from_item(pair.into_inner(), metadata),
-            Rule::join_clause => analyze_join_clause(pair.into_inner(), metadata),
-            Rule::projection => analyze_projection(pair.into_inner(), metadata),
-            Rule::where_clause => analyze_where_clause(pair.into_inner(), metadata),
-            _ => analyze_pairs(pair.into_inner(), metadata),
+            Rule::from_item | Rule::join_clause => {
+                for item in pair.into_inner() {
+                    if let Rule::table_factor = item.as_rule() {
+                        for name in item.into_inner() {
+                            if let Rule::qualified_identifier | Rule::alias_identifier =
+                                name.as_rule()
+                            {
+                                scope.insert(name.as_str().to_string());
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
         }
     }
+
+    scope
 }
 
-/// Analyze FROM clause items
-fn analyze_from_item(pairs: pest::iterators::Pairs<Rule>, metadata: &mut QueryMetadata) {
+/// Find `outer.col = inner.col` comparisons, not descending into deeper subqueries
+fn find_correlated_equalities(
+    pairs: pest::iterators::Pairs<Rule>,
+    scope: &HashSet<String>,
+    outer_scopes: &[HashSet<String>],
+    correlations: &mut Vec<(String, String)>,
+) {
     for pair in pairs {
-        if let Rule::table_factor = pair.as_rule() {
-            analyze_table_factor(pair.into_inner(), metadata);
+        match pair.as_rule() {
+            Rule::compound_select => {}
+            Rule::comparison => {
+                let mut inner = pair.clone().into_inner();
+                let left = inner.next().and_then(bare_column);
+                let suffix = inner.next();
+                let right = suffix.as_ref().and_then(|suffix| {
+                    let mut parts = suffix.clone().into_inner();
+                    match (parts.next(), parts.next()) {
+                        (Some(op), Some(operand)) if op.as_str() == "=" => bare_column(operand),
+                        _ => None,
+                    }
+                });
+
+                if let (Some(left), Some(right), None) = (left, right, inner.next()) {
+                    let is_inner = |column: &str| {
+                        column_qualifier(column).is_some_and(|table| scope.contains(table))
+                    };
+                    let is_outer = |column: &str| {
+                        column_qualifier(column).is_some_and(|table| {
+                            !scope.contains(table)
+                                && outer_scopes.iter().any(|outer| outer.contains(table))
+                        })
+                    };
+
+                    if is_outer(&left) && is_inner(&right) {
+                        correlations.push((left, right));
+                    } else if is_inner(&left) && is_outer(&right) {
+                        correlations.push((right, left));
+                    }
+                }
+
+                find_correlated_equalities(pair.into_inner(), scope, outer_scopes, correlations);
+            }
+            _ => find_correlated_equalities(pair.into_inner(), scope, outer_scopes, correlations),
         }
     }
 }
 
-/// Analyze table references and their aliases
-fn analyze_table_factor(pairs: pest::iterators::Pairs<Rule>, metadata: &mut QueryMetadata) {
-    let mut table_name = None;
-    let mut alias = None;
+/// Describe each projection item of a SELECT as a result column
+fn select_output_columns(select: pest::iterators::Pair<Rule>) -> Vec<OutputColumn> {
+    let Some(projection) = select
+        .into_inner()
+        .find(|pair| pair.as_rule() == Rule::projection)
+    else {
+        return Vec::new();
+    };
+    let Some(list) = projection.clone().into_inner().next() else {
+        return vec![OutputColumn {
+            name: None,
+            expr: projection.as_str().trim_end().to_string(),
+            alias: None,
+        }];
+    };
 
-    for pair in pairs {
-        match pair.as_rule() {
-            Rule::identifier if table_name.is_none() => {
-                table_name = Some(pair.as_str().to_string());
+    list.into_inner()
+        .map(|item| {
+            let mut expr = None;
+            let mut alias = None;
+            for part in item.into_inner() {
+                match part.as_rule() {
+                    Rule::alias => alias = Some(part.as_str().trim_end().to_string()),
+                    _ => expr = Some(part),
+                }
             }
-            Rule::identifier => {
-                alias = Some(pair.as_str().to_string());
+
+            let name = alias.clone().or_else(|| {
+                expr.clone().and_then(|expr| {
+                    bare_column(expr.clone())
+                        .or_else(|| bare_function_name(expr))
+                        .map(|name| name.rsplit('.').next().unwrap_or_default().to_string())
+                })
+            });
+
+            OutputColumn {
+                name,
+                expr: expr.map_or_else(String::new, |expr| expr.as_str().trim_end().to_string()),
+                alias,
             }
-            Rule::alias_identifier => {
-                alias = Some(pair.as_str().to_string());
+        })
+        .collect()
+}
+
+/// Common table expressions in scope: name -> (declared column names, body)
+type CteScope<'i> = HashMap<String, (Option<Vec<String>>, pest::iterators::Pair<'i, Rule>)>;
+
+/// Result columns of a compound SELECT as described by [`result_columns`]
+fn compound_result_columns<'i>(
+    compound: pest::iterators::Pair<'i, Rule>,
+    outer_scope: &CteScope<'i>,
+) -> Vec<OutputColumn> {
+    let mut scope = outer_scope.clone();
+    let mut leading = None;
+
+    for part in compound.into_inner() {
+        match part.as_rule() {
+            Rule::with_clause => {
+                for cte in part.into_inner().filter(|pair| pair.as_rule() == Rule::cte) {
+                    let mut inner = cte.into_inner();
+                    let Some(name) = inner.next() else {
+                        continue;
+                    };
+                    let columns = inner
+                        .clone()
+                        .find(|pair| pair.as_rule() == Rule::column_list)
+                        .map(|list| {
+                            list.into_inner()
+                                .flat_map(|identifiers| identifiers.into_inner())
+                                .map(|identifier| identifier.as_str().trim_end().to_string())
+                                .collect()
+                        });
+                    if let Some(body) = inner.find(|pair| pair.as_rule() == Rule::compound_select) {
+                        scope.insert(name.as_str().to_string(), (columns, body));
+                    }
+                }
             }
-            _ => analyze_pairs(pair.into_inner(), metadata),
+            Rule::select_stmt if leading.is_none() => leading = Some(part),
+            Rule::intersect_select if leading.is_none() => leading = part.into_inner().next(),
+            _ => {}
         }
     }
 
-    if let Some(table) = table_name {
-        metadata.tables.insert(table.clone());
-        if let Some(alias_name) = alias {
-            metadata.aliases.insert(alias_name, table);
+    let Some(select) = leading else {
+        return Vec::new();
+    };
+    let columns = select_output_columns(select.clone());
+    if columns.len() != 1 || columns[0].expr != "*" {
+        return columns;
+    }
+
+    // Only `SELECT * FROM source` without joins passes the source's columns through
+    let mut sources = select
+        .into_inner()
+        .filter(|pair| matches!(pair.as_rule(), Rule::from_item | Rule::join_clause));
+    let (Some(from_item), None) = (sources.next(), sources.next()) else {
+        return columns;
+    };
+    let mut table_factors = from_item.into_inner();
+    let (Some(table_factor), None) = (table_factors.next(), table_factors.next()) else {
+        return columns;
+    };
+
+    match table_factor.into_inner().next() {
+        Some(source) if source.as_rule() == Rule::compound_select => {
+            compound_result_columns(source, &scope)
         }
+        Some(source) if source.as_rule() == Rule::qualified_identifier => {
+            let Some((names, body)) = scope.get(source.as_str()).cloned() else {
+                return columns;
+            };
+            let body_columns = compound_result_columns(body, &scope);
+            match names {
+                Some(names) => names
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, name)| OutputColumn {
+                        name: Some(name.clone()),
+                        expr: body_columns
+                            .get(index)
+                            .map_or_else(|| name.clone(), |column| column.expr.clone()),
+                        alias: Some(name),
+                    })
+                    .collect(),
+                None => body_columns,
+            }
+        }
+        _ => columns,
     }
 }
 
-/// Analyze JOIN clauses and extract join information
-fn analyze_join_clause(pairs: pest::iterators::Pairs<Rule>, metadata: &mut QueryMetadata) {
-    let mut join_type = None;
-    let mut table = None;
-    let mut alias = None;
-    let mut condition = String::new();
+/// Return the function name when an expression consists of nothing but a function call
+fn bare_function_name(pair: pest::iterators::Pair<Rule>) -> Option<String> {
+    let text = pair.as_str().trim_end();
+    let mut current = pair;
 
-    for pair in pairs {
-        match pair.as_rule() {
-            Rule::JOIN_TYPE => join_type = Some(pair.as_str().to_string()),
-            Rule::table_factor => {
-                for inner_pair in pair.into_inner() {
-                    match inner_pair.as_rule() {
-                        Rule::identifier if table.is_none() => {
-                            table = Some(inner_pair.as_str().to_string());
-                        }
-                        Rule::identifier => {
-                            alias = Some(inner_pair.as_str().to_string());
-                        }
-                        Rule::alias_identifier => {
-                            alias = Some(inner_pair.as_str().to_string());
-                        }
-                        _ => analyze_pairs(inner_pair.into_inner(), metadata),
-                    }
+    loop {
+        if let Rule::function_call = current.as_rule() {
+            if current.as_str().trim_end() != text {
+                return None;
+            }
+            return current
+                .into_inner()
+                .next()
+                .map(|name| name.as_str().trim_end().to_string());
+        }
+
+        let mut inner = current.into_inner();
+        match (inner.next(), inner.next()) {
+            (Some(child), None) => current = child,
+            _ => return None,
+        }
+    }
+}
+
+/// Canonical form of an identifier: quotes removed from quoted parts, unquoted parts folded
+fn fold_identifier(name: &str, dialect: Dialect) -> String {
+    split_qualified(name)
+        .iter()
+        .map(|part| unquote_part(part).unwrap_or_else(|| fold_case(part, dialect)))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Fold an unquoted identifier to the case the dialect stores it in
+fn fold_case(name: &str, dialect: Dialect) -> String {
+    match dialect {
+        Dialect::Generic => name.to_uppercase(),
+        Dialect::Postgres | Dialect::Sqlite | Dialect::MsSql => name.to_lowercase(),
+        Dialect::MySql => name.to_string(),
+    }
+}
+
+/// Name inside a `"quoted"`, `` `quoted` ``, or `[quoted]` identifier with doubled closing
+/// quotes unescaped; `None` when the identifier is not quoted
+fn unquote_part(part: &str) -> Option<String> {
+    let (_, close) = [('"', '"'), ('`', '`'), ('[', ']')]
+        .into_iter()
+        .find(|(open, close)| {
+            part.len() >= 2 && part.starts_with(*open) && part.ends_with(*close)
+        })?;
+    Some(part[1..part.len() - 1].replace(&format!("{close}{close}"), &close.to_string()))
+}
+
+/// Dotted name with every quoted part unquoted; the unquoted parts are added to `quoted`
+fn canonical_name(name: &str, quoted: &mut HashSet<String>) -> String {
+    split_qualified(name)
+        .into_iter()
+        .map(|part| match unquote_part(&part) {
+            Some(name) => {
+                quoted.insert(name.clone());
+                name
+            }
+            None => part,
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Split a dotted name into its parts, ignoring dots inside quoted identifiers
+fn split_qualified(text: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut closing = None;
+
+    for ch in text.chars() {
+        match closing {
+            Some(quote) if ch == quote => closing = None,
+            Some(_) => {}
+            None => match ch {
+                '.' => {
+                    parts.push(std::mem::take(&mut current));
+                    continue;
                 }
+                '"' | '`' => closing = Some(ch),
+                '[' => closing = Some(']'),
+                _ => {}
+            },
+        }
+        current.push(ch);
+    }
+
+    parts.push(current);
+    parts
+}
+
+/// Type of an expression as described by [`infer_output_types`]
+fn infer_expr_type(
+    pair: pest::iterators::Pair<Rule>,
+    schema: &Schema,
+    metadata: &QueryMetadata,
+) -> Option<String> {
+    let mut current = pair;
+    loop {
+        let mut inner = current.clone().into_inner();
+        match (inner.next(), inner.next()) {
+            (Some(child), None) if child.as_str().trim_end() == current.as_str().trim_end() => {
+                current = child
             }
-            Rule::ON_KEY => {}
-            _ => {
-                condition = pair.as_str().to_string();
-                analyze_expression_for_metadata(pair.into_inner(), metadata);
+            _ => break,
+        }
+    }
+
+    match current.as_rule() {
+        Rule::number if current.as_str().contains('.') => Some("numeric".to_string()),
+        Rule::number => Some("integer".to_string()),
+        Rule::string => Some("text".to_string()),
+        Rule::boolean => Some("boolean".to_string()),
+        Rule::column => column_type(current.as_str().trim_end(), schema, metadata),
+        Rule::unary => current
+            .into_inner()
+            .filter(|part| part.as_rule() == Rule::cast_suffix)
+            .last()
+            .map(|cast| cast.as_str().trim_end()[2..].trim_start().to_lowercase()),
+        Rule::cast_expr => current
+            .into_inner()
+            .find(|part| part.as_rule() == Rule::type_name)
+            .map(|data_type| data_type.as_str().trim_end().to_lowercase()),
+        Rule::function_call => {
+            let mut parts = current.into_inner();
+            let name = parts.next()?.as_str().trim_end().to_uppercase();
+            let name = name.rsplit('.').next().unwrap_or_default();
+            let argument_type = || {
+                parts
+                    .clone()
+                    .find(|part| part.as_rule() == Rule::expr)
+                    .and_then(|argument| infer_expr_type(argument, schema, metadata))
+            };
+            match name {
+                "COUNT" => Some("bigint".to_string()),
+                "AVG" => Some("numeric".to_string()),
+                "MIN" | "MAX" => argument_type(),
+                "SUM" => argument_type().map(|argument| match argument.as_str() {
+                    "smallint" | "integer" | "int" => "bigint".to_string(),
+                    "bigint" | "decimal" => "numeric".to_string(),
+                    _ => argument,
+                }),
+                _ => None,
             }
         }
+        // A CASE takes the type of its first branch whose type is known
+        Rule::case_expr => current
+            .into_inner()
+            .filter_map(|part| match part.as_rule() {
+                Rule::when_clause => part.into_inner().nth(1),
+                Rule::else_clause => part.into_inner().next(),
+                _ => None,
+            })
+            .find_map(|result| infer_expr_type(result, schema, metadata)),
+        _ => None,
     }
+}
 
-    if let Some(table_name) = table {
-        if let Some(alias_name) = alias.clone() {
-            metadata.aliases.insert(alias_name, table_name.clone());
+/// Schema type of a column reference, resolving its qualifier through the query's aliases
+fn column_type(column: &str, schema: &Schema, metadata: &QueryMetadata) -> Option<String> {
+    let column = &canonical_name(column, &mut HashSet::new());
+    if let Some((qualifier, name)) = column.rsplit_once('.') {
+        let table = metadata
+            .aliases
+            .get(qualifier)
+            .map_or(qualifier, String::as_str);
+        return schema.get(&format!("{table}.{name}")).cloned();
+    }
+
+    let tables = metadata
+        .tables
+        .iter()
+        .chain(metadata.joins.iter().map(|join| &join.table));
+    let types: HashSet<&String> = tables
+        .filter_map(|table| schema.get(&format!("{table}.{column}")))
+        .collect();
+    let mut types = types.into_iter();
+    match (types.next(), types.next()) {
+        (Some(column_type), None) => Some(column_type.clone()),
+        _ => None,
+    }
+}
+
+/// Return the column text when an expression consists of nothing but a column reference
+fn bare_column(pair: pest::iterators::Pair<Rule>) -> Option<String> {
+    let text = pair.as_str().trim_end();
+    let mut current = pair;
+
+    loop {
+        if let Rule::column = current.as_rule() {
+            return (current.as_str() == text).then(|| text.to_string());
         }
 
-        metadata.joins.push(JoinInfo {
-            join_type,
-            table: table_name,
-            alias,
-            condition,
-        });
+        let mut inner = current.into_inner();
+        match (inner.next(), inner.next()) {
+            (Some(child), None) => current = child,
+            _ => return None,
+        }
     }
 }
 
-/// Analyze SELECT projection (column list or *)
-fn analyze_projection(pairs: pest::iterators::Pairs<Rule>, metadata: &mut QueryMetadata) {
+/// Table or alias qualifier of a `table.column` reference, including any schema prefix
+fn column_qualifier(column: &str) -> Option<&str> {
+    column.rsplit_once('.').map(|(table, _)| table)
+}
+
+fn collect_ungrouped_columns(pairs: pest::iterators::Pairs<Rule>, violations: &mut Vec<String>) {
     for pair in pairs {
-        match pair.as_rule() {
-            Rule::projection_list => {
-                for item in pair.into_inner() {
-                    if let Rule::projection_item = item.as_rule() {
-                        analyze_projection_item(item.into_inner(), metadata);
-                    }
-                }
-            }
-            _ => analyze_pairs(pair.into_inner(), metadata),
+        if pair.as_rule() == Rule::select_stmt {
+            check_select_grouping(pair.clone(), violations);
         }
+        collect_ungrouped_columns(pair.into_inner(), violations);
     }
 }
 
-/// Analyze individual projection items (columns, expressions)
-fn analyze_projection_item(pairs: pest::iterators::Pairs<Rule>, metadata: &mut QueryMetadata) {
-    for pair in pairs {
+/// Flag projected columns of one SELECT that escape its grouping
+fn check_select_grouping(select: pest::iterators::Pair<Rule>, violations: &mut Vec<String>) {
+    let mut grouped = None;
+    let mut projection = None;
+
+    for pair in select.into_inner() {
         match pair.as_rule() {
-            Rule::expr => analyze_expression_for_metadata(pair.into_inner(), metadata),
-            _ => analyze_pairs(pair.into_inner(), metadata),
+            Rule::group_by_clause => {
+                grouped = Some(
+                    pair.into_inner()
+                        .flat_map(|list| list.into_inner())
+                        .map(|expr| expr.as_str().trim_end().to_string())
+                        .collect::<HashSet<_>>(),
+                );
+            }
+            Rule::projection => projection = Some(pair),
+            _ => {}
         }
     }
-}
 
-/// Analyze WHERE clause expressions
-fn analyze_where_clause(pairs: pest::iterators::Pairs<Rule>, metadata: &mut QueryMetadata) {
-    for pair in pairs {
-        if let Rule::expr = pair.as_rule() {
-            analyze_expression_for_metadata(pair.into_inner(), metadata);
+    let Some(projection) = projection else {
+        return;
+    };
+    let mut columns = Vec::new();
+    let mut has_aggregate = false;
+    collect_unaggregated_columns(projection.into_inner(), &mut columns, &mut has_aggregate);
+
+    let grouped = match grouped {
+        Some(grouped) => grouped,
+        None if has_aggregate => HashSet::new(),
+        None => return,
+    };
+
+    for column in columns {
+        let name = column.rsplit('.').next().unwrap_or(&column);
+        if !grouped.contains(&column) && !grouped.contains(name) && !violations.contains(&column) {
+            violations.push(column);
         }
     }
 }
 
-/// Extract metadata from expressions (columns, functions, tables)
-fn analyze_expression_for_metadata(
+/// Collect columns used outside aggregate calls, descending into scalar function arguments
+fn collect_unaggregated_columns(
     pairs: pest::iterators::Pairs<Rule>,
-    metadata: &mut QueryMetadata,
+    columns: &mut Vec<String>,
+    has_aggregate: &mut bool,
 ) {
     for pair in pairs {
         match pair.as_rule() {
-            Rule::column => {
-                metadata.columns.insert(pair.as_str().to_string());
-            }
+            Rule::column => columns.push(pair.as_str().trim_end().to_string()),
             Rule::function_call => {
-                let func_name = pair.as_str().split('(').next().unwrap_or("").to_string();
-                metadata.functions.insert(func_name.clone());
-
-                let aggregates = ["SUM", "COUNT", "AVG", "MIN", "MAX"];
-                if aggregates.contains(&func_name.to_uppercase().as_str()) {
-                    metadata.aggregates.insert(func_name);
-                }
-            }
-            Rule::identifier => {
-                if !metadata.aliases.contains_key(pair.as_str()) {
-                    metadata.tables.insert(pair.as_str().to_string());
+                let name = pair.as_str().split('(').next().unwrap_or("");
+                if is_aggregate_function(name) {
+                    *has_aggregate = true;
+                } else {
+                    let arguments = pair
+                        .into_inner()
+                        .filter(|inner| inner.as_rule() != Rule::window_spec);
+                    for argument in arguments {
+                        collect_unaggregated_columns(argument.into_inner(), columns, has_aggregate);
+                    }
                 }
             }
-            _ => analyze_expression_for_metadata(pair.into_inner(), metadata),
+            Rule::compound_select => {}
+            _ => collect_unaggregated_columns(pair.into_inner(), columns, has_aggregate),
         }
     }
 }
 
-/// Analyze INSERT statements
-fn analyze_insert_stmt(pairs: pest::iterators::Pairs<Rule>, metadata: &mut QueryMetadata) {
+/// Record the opening quote character of every quoted identifier
+fn collect_quote_styles(pairs: pest::iterators::Pairs<Rule>, styles: &mut HashSet<char>) {
     for pair in pairs {
         match pair.as_rule() {
-            Rule::identifier => {
-                metadata.tables.insert(pair.as_str().to_string());
-            }
-            Rule::expr => {
-                analyze_expression_for_metadata(pair.into_inner(), metadata);
-            }
-            _ => analyze_pairs(pair.into_inner(), metadata),
+            Rule::identifier
+            | Rule::qualified_identifier
+            | Rule::alias
+            | Rule::alias_identifier
+            | Rule::column
+            | Rule::qualified_wildcard => identifier_quote_styles(pair.as_str(), styles),
+            _ => collect_quote_styles(pair.into_inner(), styles),
         }
     }
 }
 
-/// Analyze UPDATE statements
-fn analyze_update_stmt(pairs: pest::iterators::Pairs<Rule>, metadata: &mut QueryMetadata) {
-    for pair in pairs {
-        match pair.as_rule() {
-            Rule::identifier => {
-                metadata.tables.insert(pair.as_str().to_string());
-            }
-            Rule::set_list => {
-                analyze_set_list(pair.into_inner(), metadata);
-            }
-            Rule::where_clause => {
-                analyze_where_clause(pair.into_inner(), metadata);
-            }
-            _ => analyze_pairs(pair.into_inner(), metadata),
+/// Record the opening quote character of every quoted part of a dotted name
+fn identifier_quote_styles(text: &str, styles: &mut HashSet<char>) {
+    for part in split_qualified(text) {
+        if unquote_part(&part).is_some() {
+            styles.extend(part.chars().next());
         }
     }
 }
 
-/// Analyze DELETE statements
-fn analyze_delete_stmt(pairs: pest::iterators::Pairs<Rule>, metadata: &mut QueryMetadata) {
+/// 64-bit FNV-1a hash, used where a hash must not change between runs
+fn fnv1a_64(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// How literal values appear in canonical query text
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LiteralStyle {
+    /// Keep the literal as written
+    Keep,
+    /// Replace the literal with a placeholder
+    Placeholder(PlaceholderStyle),
+}
+
+/// Kind of a token in canonical query text, used to decide spacing
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TokenKind {
+    Keyword,
+    Name,
+    Literal,
+    Symbol,
+}
+
+/// Render parsed pairs as canonical text
+///
+/// Keywords and punctuation are not part of the parse tree, so they are recovered from the
+/// input text between the tree's leaves.
+fn render_canonical(
+    input: &str,
+    pairs: pest::iterators::Pairs<Rule>,
+    style: LiteralStyle,
+) -> String {
+    join_canonical_tokens(&canonical_tokens(input, pairs, style))
+}
+
+/// Split a parsed query into canonical tokens, rendering literals in the given style
+fn canonical_tokens(
+    input: &str,
+    pairs: pest::iterators::Pairs<Rule>,
+    style: LiteralStyle,
+) -> Vec<(TokenKind, String)> {
+    let mut tokens = Vec::new();
+    let mut cursor = 0;
+
+    collect_canonical_tokens(input, pairs.clone(), style, &mut cursor, &mut tokens);
+    push_gap_tokens(&input[cursor..], &mut tokens);
+
+    if let LiteralStyle::Placeholder(placeholder_style) = style {
+        let mut bind_params = BindSummary::default();
+        collect_bind_params(pairs, &mut bind_params);
+
+        let literals = tokens
+            .iter_mut()
+            .filter(|(kind, _)| *kind == TokenKind::Literal);
+        for (index, (_, text)) in literals.enumerate() {
+            *text = match placeholder_style {
+                PlaceholderStyle::Question => "?".to_string(),
+                PlaceholderStyle::Dollar => format!("${}", bind_params.max_positional + index + 1),
+                PlaceholderStyle::Colon => format!(":p{}", index + 1),
+            };
+        }
+    }
+
+    tokens
+}
+
+fn collect_canonical_tokens(
+    input: &str,
+    pairs: pest::iterators::Pairs<Rule>,
+    style: LiteralStyle,
+    cursor: &mut usize,
+    tokens: &mut Vec<(TokenKind, String)>,
+) {
     for pair in pairs {
-        match pair.as_rule() {
-            Rule::identifier => {
-                metadata.tables.insert(pair.as_str().to_string());
-            }
-            Rule::where_clause => {
-                analyze_where_clause(pair.into_inner(), metadata);
+        let span = pair.as_span();
+
+        if pair.as_rule() == Rule::in_rhs {
+            if let Some(values) = literal_in_list(&pair) {
+                push_gap_tokens(&input[*cursor..span.start()], tokens);
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        tokens.push((TokenKind::Symbol, ",".to_string()));
+                    }
+                    tokens.push((TokenKind::Literal, render_literal(value, style)));
+                }
+                *cursor = span.end();
+                continue;
             }
-            _ => analyze_pairs(pair.into_inner(), metadata),
         }
+
+        if pair.as_rule() == Rule::literal {
+            push_gap_tokens(&input[*cursor..span.start()], tokens);
+            tokens.push((
+                TokenKind::Literal,
+                render_literal(pair.as_str().trim_end(), style),
+            ));
+            *cursor = span.end();
+            continue;
+        }
+
+        if pair.clone().into_inner().next().is_some() {
+            collect_canonical_tokens(input, pair.into_inner(), style, cursor, tokens);
+            continue;
+        }
+
+        push_gap_tokens(&input[*cursor..span.start()], tokens);
+        let text = pair.as_str().trim_end();
+        match pair.as_rule() {
+            Rule::identifier
+            | Rule::qualified_identifier
+            | Rule::function_name
+            | Rule::alias
+            | Rule::alias_identifier
+            | Rule::column
+            | Rule::anonymous_parameter
+            | Rule::positional_parameter
+            | Rule::named_parameter => tokens.push((TokenKind::Name, text.to_string())),
+            _ => push_gap_tokens(text, tokens),
+        }
+        *cursor = span.end();
     }
 }
 
-/// Analyze SET clause in UPDATE statements
-fn analyze_set_list(pairs: pest::iterators::Pairs<Rule>, metadata: &mut QueryMetadata) {
-    for pair in pairs {
-        if let Rule::set_item = pair.as_rule() {
-            analyze_set_item(pair.into_inner(), metadata);
+/// Tokenize keywords and punctuation between leaves, dropping whitespace, comments, and `;`
+fn push_gap_tokens(gap: &str, tokens: &mut Vec<(TokenKind, String)>) {
+    let mut rest = gap;
+
+    while let Some(ch) = rest.chars().next() {
+        if ch.is_whitespace() || ch == '\u{FEFF}' || ch == ';' {
+            rest = &rest[ch.len_utf8()..];
+        } else if rest.starts_with("--") {
+            rest = rest.find('\n').map_or("", |end| &rest[end..]);
+        } else if rest.starts_with("/*") {
+            rest = rest.find("*/").map_or("", |end| &rest[end + 2..]);
+        } else if ch.is_ascii_alphanumeric() || ch == '_' {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            tokens.push((TokenKind::Keyword, rest[..end].to_ascii_uppercase()));
+            rest = &rest[end..];
+        } else {
+            let len = [
+                "<=>", "!~*", "<=", ">=", "<>", "!=", "!~", "~*", "||", "::", "[]",
+            ]
+            .iter()
+            .find(|op| rest.starts_with(*op))
+            .map_or(ch.len_utf8(), |op| op.len());
+            tokens.push((TokenKind::Symbol, rest[..len].to_string()));
+            rest = &rest[len..];
         }
     }
 }
 
-/// Analyze individual SET items (column = value)
-fn analyze_set_item(pairs: pest::iterators::Pairs<Rule>, metadata: &mut QueryMetadata) {
-    for pair in pairs {
-        match pair.as_rule() {
-            Rule::identifier => {
-                metadata.columns.insert(pair.as_str().to_string());
-            }
-            Rule::expr => {
-                analyze_expression_for_metadata(pair.into_inner(), metadata);
+fn normalize_gap_tokens(text: &str) -> String {
+    let mut tokens = Vec::new();
+    push_gap_tokens(text, &mut tokens);
+    tokens
+        .into_iter()
+        .map(|(_, token)| token)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn join_canonical_tokens(tokens: &[(TokenKind, String)]) -> String {
+    let mut output = String::new();
+    let mut previous: Option<&(TokenKind, String)> = None;
+
+    for token in tokens {
+        if let Some((previous_kind, previous_text)) = previous {
+            let glued = matches!(token.1.as_str(), "," | ")" | "." | "::" | "[]")
+                || matches!(previous_text.as_str(), "(" | "." | "::")
+                || (token.1 == "("
+                    && (*previous_kind == TokenKind::Name
+                        || previous_text.eq_ignore_ascii_case("CAST")));
+            if !glued {
+                output.push(' ');
             }
-            _ => analyze_pairs(pair.into_inner(), metadata),
+        }
+        output.push_str(&token.1);
+        previous = Some(token);
+    }
+
+    output
+}
+
+fn render_literal(text: &str, style: LiteralStyle) -> String {
+    match style {
+        LiteralStyle::Placeholder(_) => "?".to_string(),
+        LiteralStyle::Keep if text.starts_with('\'') => text.to_string(),
+        LiteralStyle::Keep => text.to_ascii_uppercase(),
+    }
+}
+
+/// Sorted, deduplicated values of an `IN` list made up only of literals
+fn literal_in_list(in_rhs: &pest::iterators::Pair<Rule>) -> Option<Vec<String>> {
+    let expr_list = in_rhs.clone().into_inner().next()?;
+    if expr_list.as_rule() != Rule::expr_list {
+        return None;
+    }
+
+    let mut values = expr_list
+        .into_inner()
+        .map(bare_literal)
+        .collect::<Option<Vec<_>>>()?;
+    values.sort_by(|a, b| match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(x), Ok(y)) => x.total_cmp(&y),
+        (Ok(_), Err(_)) => std::cmp::Ordering::Less,
+        (Err(_), Ok(_)) => std::cmp::Ordering::Greater,
+        (Err(_), Err(_)) => a.cmp(b),
+    });
+    values.dedup();
+
+    Some(values)
+}
+
+/// Return the literal text when an expression consists of nothing but a literal
+fn bare_literal(pair: pest::iterators::Pair<Rule>) -> Option<String> {
+    let text = pair.as_str().trim_end();
+    let mut current = pair;
+
+    loop {
+        if let Rule::literal = current.as_rule() {
+            return (current.as_str().trim_end() == text).then(|| text.to_string());
+        }
+
+        let mut inner = current.into_inner();
+        match (inner.next(), inner.next()) {
+            (Some(child), None) => current = child,
+            _ => return None,
         }
     }
 }
@@ -1,8 +1,10 @@
 use clap::{Parser, Subcommand};
 use std::fs;
-use std::io::{self, Read};
+use std::io::{self, Read, Write};
 
-use lialoonk_sql_query_parser::{analyze_sql, analyze_sql_json, parse_sql};
+use lialoonk_sql_query_parser::{
+    analyze_sql_with_dialect, parse_sql_with_dialect, print_tree_with_dialect, Dialect,
+};
 
 /// SQL Query Parser - A tool for parsing and analyzing SQL queries
 #[derive(Parser)]
@@ -26,6 +28,12 @@ enum Commands {
 
         #[arg(long, default_value = "parse")]
         format: String,
+
+        #[arg(short, long)]
+        output: Option<String>,
+
+        #[arg(long, default_value = "ansi")]
+        dialect: Dialect,
     },
     Help,
     Credits,
@@ -39,6 +47,8 @@ fn main() {
             query,
             file,
             format,
+            output,
+            dialect,
         } => {
             let sql_query = match (query, file) {
                 (Some(q), None) => q,
@@ -70,8 +80,37 @@ fn main() {
                 std::process::exit(1);
             }
 
+            if let Some(path) = output {
+                if format != "json" {
+                    eprintln!("Error: --output is only supported with --format json");
+                    std::process::exit(1);
+                }
+                let mut writer = match fs::File::create(&path) {
+                    Ok(file) => io::BufWriter::new(file),
+                    Err(e) => {
+                        eprintln!("Error creating output file '{}': {}", path, e);
+                        std::process::exit(1);
+                    }
+                };
+                let written = analyze_sql_with_dialect(&sql_query, dialect)
+                    .map_err(|error| error.to_string())
+                    .and_then(|metadata| {
+                        serde_json::to_writer_pretty(&mut writer, &metadata)
+                            .map_err(|error| error.to_string())
+                    });
+                if let Err(error) = written {
+                    eprintln!("Failed to generate JSON: {}", error);
+                    std::process::exit(1);
+                }
+                if let Err(e) = writer.flush() {
+                    eprintln!("Error writing output file '{}': {}", path, e);
+                    std::process::exit(1);
+                }
+                return;
+            }
+
             match format.as_str() {
-                "parse" => match parse_sql(&sql_query) {
+                "parse" => match parse_sql_with_dialect(&sql_query, dialect) {
                     Ok(pairs) => {
                         println!("Parse tree:\n {:#?}", pairs);
                     }
@@ -80,7 +119,16 @@ fn main() {
                         std::process::exit(1);
                     }
                 },
-                "analyze" => match analyze_sql(&sql_query) {
+                "tree" => match print_tree_with_dialect(&sql_query, dialect) {
+                    Ok(tree) => {
+                        print!("{}", tree);
+                    }
+                    Err(error) => {
+                        eprintln!("Failed to parse SQL query: {}", error);
+                        std::process::exit(1);
+                    }
+                },
+                "analyze" => match analyze_sql_with_dialect(&sql_query, dialect) {
                     Ok(metadata) => {
                         println!("SQL Query Analysis:");
                         println!("Tables: {:?}", metadata.tables);
@@ -95,7 +143,11 @@ fn main() {
                         std::process::exit(1);
                     }
                 },
-                "json" => match analyze_sql_json(&sql_query) {
+                "json" => match analyze_sql_with_dialect(&sql_query, dialect)
+                    .map_err(|error| error.to_string())
+                    .and_then(|metadata| {
+                        serde_json::to_string_pretty(&metadata).map_err(|error| error.to_string())
+                    }) {
                     Ok(json) => {
                         println!("{}", json);
                     }
@@ -106,7 +158,7 @@ fn main() {
                 },
                 _ => {
                     eprintln!(
-                        "Error: Invalid format '{}'. Use 'parse', 'analyze', or 'json'",
+                        "Error: Invalid format '{}'. Use 'parse', 'tree', 'analyze', or 'json'",
                         format
                     );
                     std::process::exit(1);
@@ -140,12 +192,25 @@ fn print_help() {
     println!("PARSE OPTIONS:");
     println!("    -q, --query <QUERY>    SQL query to parse");
     println!("    -f, --file <FILE>      Read SQL query from file");
-    println!("        --format <FORMAT>  Output format: parse, analyze, or json [default: parse]");
+    println!(
+        "        --format <FORMAT>  Output format: parse, tree, analyze, or json [default: parse]"
+    );
+    println!("    -o, --output <FILE>    Write JSON output to a file (requires --format json)");
+    println!(
+        "        --dialect <NAME>   SQL dialect: ansi, postgres, mysql, sqlite, or mssql [default: ansi]"
+    );
     println!();
     println!("EXAMPLES:");
     println!("    lialoonk-sql-query-parser parse --query \"SELECT * FROM users\"");
     println!("    lialoonk-sql-query-parser parse --file query.sql --format analyze");
+    println!("    lialoonk-sql-query-parser parse --query \"SELECT id FROM users\" --format tree");
     println!("    echo \"SELECT * FROM users\" | lialoonk-sql-query-parser parse --format json");
+    println!(
+        "    lialoonk-sql-query-parser parse --query \"SELECT * FROM t USE INDEX (i)\" --dialect mysql"
+    );
+    println!(
+        "    lialoonk-sql-query-parser parse --file query.sql --format json --output out.json"
+    );
     println!("    lialoonk-sql-query-parser help");
     println!("    lialoonk-sql-query-parser credits");
     println!();
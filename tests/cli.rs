@@ -0,0 +1,57 @@
+use anyhow::Result;
+use std::process::Command;
+
+fn run_cli(args: &[&str]) -> Result<std::process::Output> {
+    Ok(
+        Command::new(env!("CARGO_BIN_EXE_lialoonk-sql-query-parser"))
+            .args(args)
+            .output()?,
+    )
+}
+
+#[test]
+fn parse_with_dialect_from_help_example() -> Result<()> {
+    let output = run_cli(&[
+        "parse",
+        "--query",
+        "SELECT * FROM t USE INDEX (i)",
+        "--dialect",
+        "mysql",
+    ])?;
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.starts_with("Parse tree:"));
+    assert!(stdout.contains("index_hint"));
+
+    let output = run_cli(&["parse", "--query", "SELECT * FROM t USE INDEX (i)"])?;
+    assert!(!output.status.success());
+
+    Ok(())
+}
+
+#[test]
+fn tree_format_uses_dialect() -> Result<()> {
+    let output = run_cli(&[
+        "parse",
+        "--query",
+        "SELECT TOP 5 a FROM t",
+        "--format",
+        "tree",
+        "--dialect",
+        "mssql",
+    ])?;
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(String::from_utf8(output.stdout)?.contains("top_clause"));
+
+    Ok(())
+}
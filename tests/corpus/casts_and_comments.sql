@@ -0,0 +1,4 @@
+-- daily totals
+SELECT /*+ INDEX(events idx_ts) */ amount::numeric(10, 2), tags::text[], name || '!'
+FROM events /* trailing comment */
+WHERE id % 2 = 0
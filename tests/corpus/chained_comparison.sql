@@ -0,0 +1,2 @@
+-- expect-error: chained comparison not allowed
+SELECT * FROM t WHERE 1 < a < 5
@@ -0,0 +1,2 @@
+CREATE TABLE users (id INTEGER PRIMARY KEY, name VARCHAR(255) NOT NULL, note TEXT DEFAULT 'x')
+TABLESPACE pg_default
@@ -0,0 +1,3 @@
+CREATE VIEW active_users (id, name) AS
+SELECT id, name FROM users WHERE active = TRUE
+WITH LOCAL CHECK OPTION
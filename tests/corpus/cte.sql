@@ -0,0 +1,2 @@
+WITH recent AS (SELECT id, user_id FROM orders WHERE created_at > '2024-01-01')
+SELECT user_id, COUNT(*) FROM recent GROUP BY user_id HAVING COUNT(*) > 2
@@ -0,0 +1 @@
+DELETE FROM sessions WHERE expires_at < '2024-01-01'
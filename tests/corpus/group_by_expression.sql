@@ -0,0 +1,3 @@
+SELECT date_trunc('day', ts), COUNT(DISTINCT user_id), SUM(amount) FILTER (WHERE amount > 0)
+FROM events
+GROUP BY date_trunc('day', ts)
@@ -0,0 +1,5 @@
+SELECT u.id, o.total
+FROM users u
+LEFT OUTER JOIN orders o ON u.id = o.user_id
+CROSS JOIN regions
+INNER JOIN payments p ON p.order_id = o.id
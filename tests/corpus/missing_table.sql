@@ -0,0 +1,2 @@
+-- expect-error: expected
+DELETE users
@@ -0,0 +1,5 @@
+SELECT DISTINCT u.id, u.name AS user_name, price * 2
+FROM users AS u
+WHERE u.active = TRUE AND u.age BETWEEN 18 AND 65 AND u.email LIKE '%@example.com'
+ORDER BY u.name DESC
+LIMIT 10;
@@ -0,0 +1,7 @@
+SELECT id FROM a
+UNION ALL
+SELECT id FROM b
+INTERSECT
+SELECT id FROM c
+EXCEPT
+SELECT id FROM d
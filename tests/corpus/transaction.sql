@@ -0,0 +1 @@
+ROLLBACK TO SAVEPOINT before_update
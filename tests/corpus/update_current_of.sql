@@ -0,0 +1 @@
+UPDATE accounts SET balance = balance - 10 WHERE CURRENT OF account_cursor
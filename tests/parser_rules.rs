@@ -1,6 +1,10 @@
 use anyhow::{Context, Result};
-use lialoonk_sql_query_parser::{Rule, SqlParser};
+use lialoonk_sql_query_parser::{
+    AnalyzeOptions, Dialect, MetadataFields, OrderByItem, OutputColumn, ParseError, Predicate,
+    PreparedQuery, QualifiedColumn, QueryKind, Rule, Script, SetOpTree, SqlParser, SymbolKind,
+};
 use pest::Parser;
+use std::collections::HashSet;
 
 fn assert_rule(rule: Rule, input: &str) -> Result<()> {
     SqlParser::parse(rule, input)
@@ -84,6 +88,850 @@ fn test_delete_analysis() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_having_predicates() -> Result<()> {
+    let query = "SELECT dept, COUNT(*) FROM emp GROUP BY dept HAVING COUNT(*) > 1";
+    let metadata = lialoonk_sql_query_parser::analyze_sql(query)?;
+
+    assert!(metadata.where_predicates.is_empty());
+    assert_eq!(
+        metadata.having_predicates,
+        vec![Predicate {
+            left: "COUNT(*)".to_string(),
+            operator: ">".to_string(),
+            right: Some("1".to_string()),
+        }]
+    );
+    assert!(metadata.aggregates.contains("COUNT"));
+
+    let query = "SELECT dept FROM emp WHERE name NOT IN ('a', 'b') AND manager IS NULL \
+                 GROUP BY dept HAVING SUM(salary) > 100";
+    let metadata = lialoonk_sql_query_parser::analyze_sql(query)?;
+
+    assert_eq!(
+        metadata.where_predicates,
+        vec![
+            Predicate {
+                left: "name".to_string(),
+                operator: "NOT IN".to_string(),
+                right: Some("('a', 'b')".to_string()),
+            },
+            Predicate {
+                left: "manager".to_string(),
+                operator: "IS NULL".to_string(),
+                right: None,
+            },
+        ]
+    );
+    assert_eq!(metadata.having_predicates.len(), 1);
+    assert!(metadata.aggregates.contains("SUM"));
+
+    Ok(())
+}
+
+#[test]
+fn empty_in_list_is_rejected_unless_lenient() -> Result<()> {
+    let query = "SELECT * FROM users WHERE id IN ()";
+    let error = lialoonk_sql_query_parser::analyze_sql(query).unwrap_err();
+    assert!(error.to_string().contains("empty IN list"));
+
+    let options = AnalyzeOptions {
+        lenient: true,
+        ..AnalyzeOptions::default()
+    };
+    let metadata = lialoonk_sql_query_parser::analyze_sql_opts(query, &options)?;
+    assert!(metadata.tables.contains("users"));
+
+    Ok(())
+}
+
+#[test]
+fn test_empty_in_lists() -> Result<()> {
+    let query = "SELECT * FROM users WHERE id IN ()";
+    assert_eq!(
+        lialoonk_sql_query_parser::empty_in_lists(query)?,
+        vec!["id IN ()".to_string()]
+    );
+
+    let query = "SELECT * FROM users WHERE id IN (1, 2) AND role NOT IN ( )";
+    assert_eq!(
+        lialoonk_sql_query_parser::empty_in_lists(query)?,
+        vec!["role NOT IN ( )".to_string()]
+    );
+
+    let query = "SELECT * FROM users WHERE id IN (1)";
+    assert!(lialoonk_sql_query_parser::empty_in_lists(query)?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_bind_param_summary() -> Result<()> {
+    let query = "SELECT * FROM users WHERE id = $1 AND org_id = $3";
+    let summary = lialoonk_sql_query_parser::bind_param_summary(query)?;
+    assert_eq!(summary.max_positional, 3);
+    assert_eq!(summary.anonymous_count, 0);
+
+    let query = "SELECT * FROM users WHERE id = ? AND org_id = ?";
+    let summary = lialoonk_sql_query_parser::bind_param_summary(query)?;
+    assert_eq!(summary.anonymous_count, 2);
+    assert_eq!(summary.max_positional, 0);
+
+    let query = "UPDATE users SET name = :name WHERE id = @user_id";
+    let summary = lialoonk_sql_query_parser::bind_param_summary(query)?;
+    assert!(summary.named.contains("name"));
+    assert!(summary.named.contains("user_id"));
+
+    Ok(())
+}
+
+#[test]
+fn test_pointless_subquery_order() -> Result<()> {
+    let query = "SELECT a FROM (SELECT a FROM t ORDER BY a) s";
+    assert_eq!(
+        lialoonk_sql_query_parser::pointless_subquery_order(query)?,
+        vec!["SELECT a FROM t ORDER BY a".to_string()]
+    );
+
+    let query = "SELECT a FROM (SELECT a FROM t ORDER BY a LIMIT 10) s";
+    assert!(lialoonk_sql_query_parser::pointless_subquery_order(query)?.is_empty());
+
+    let query = "SELECT a FROM t WHERE a IN (SELECT b FROM u ORDER BY b FETCH FIRST 5 ROWS ONLY)";
+    assert!(lialoonk_sql_query_parser::pointless_subquery_order(query)?.is_empty());
+
+    let query = "SELECT a FROM t ORDER BY a";
+    assert!(lialoonk_sql_query_parser::pointless_subquery_order(query)?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn prepared_query_caches_metadata() {
+    let query = PreparedQuery::from("SELECT name FROM users WHERE id = 1");
+
+    let first = query.metadata().expect("query should parse");
+    let second = query.metadata().expect("query should parse");
+    assert!(std::ptr::eq(first, second));
+    assert!(first.tables.contains("users"));
+
+    let invalid = PreparedQuery::new("SELECT * FROM");
+    assert!(invalid.metadata().is_err());
+    assert!(std::ptr::eq(
+        invalid.metadata().unwrap_err(),
+        invalid.metadata().unwrap_err()
+    ));
+}
+
+#[test]
+fn test_niladic_functions() -> Result<()> {
+    let query = "SELECT id FROM events WHERE created = CURRENT_DATE";
+    let metadata = lialoonk_sql_query_parser::analyze_sql(query)?;
+    assert!(metadata.functions.contains("CURRENT_DATE"));
+    assert!(!metadata.columns.contains("CURRENT_DATE"));
+    assert!(metadata.columns.contains("created"));
+
+    let query = "UPDATE events SET updated_at = CURRENT_TIMESTAMP WHERE owner = CURRENT_USER";
+    let metadata = lialoonk_sql_query_parser::analyze_sql(query)?;
+    assert!(metadata.functions.contains("CURRENT_TIMESTAMP"));
+    assert!(metadata.functions.contains("CURRENT_USER"));
+    assert!(!metadata.columns.contains("CURRENT_TIMESTAMP"));
+    assert!(!metadata.columns.contains("CURRENT_USER"));
+
+    let query = "SELECT current_date_override FROM settings";
+    let metadata = lialoonk_sql_query_parser::analyze_sql(query)?;
+    assert!(metadata.columns.contains("current_date_override"));
+    assert!(metadata.functions.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn analyze_options_collect_selected_fields() -> Result<()> {
+    let query = "SELECT u.name, COUNT(*) FROM users u WHERE u.active = TRUE";
+    let options = AnalyzeOptions {
+        collect: MetadataFields::TABLES,
+        ..AnalyzeOptions::default()
+    };
+    let metadata = lialoonk_sql_query_parser::analyze_sql_opts(query, &options)?;
+
+    assert!(metadata.tables.contains("users"));
+    assert_eq!(metadata.aliases["u"], "users");
+    assert!(metadata.columns.is_empty());
+    assert!(metadata.functions.is_empty());
+    assert!(metadata.literals.is_empty());
+    assert!(metadata.where_predicates.is_empty());
+
+    let metadata = lialoonk_sql_query_parser::analyze_sql_opts(query, &AnalyzeOptions::default())?;
+    assert!(metadata.columns.contains("u.active"));
+    assert_eq!(metadata.literals, vec!["TRUE".to_string()]);
+    assert_eq!(metadata.where_predicates.len(), 1);
+
+    let query =
+        "SELECT o.id FROM orders o WHERE o.total > ? AND o.user_id IN (SELECT id FROM users)";
+    let metadata = lialoonk_sql_query_parser::analyze_sql_opts(query, &options)?;
+    assert!(metadata.tables.contains("users"));
+    assert!(metadata.parameters.is_empty());
+    assert!(metadata.qualified_columns.is_empty());
+    assert!(metadata.resolved_columns.is_empty());
+
+    let options = AnalyzeOptions {
+        collect: MetadataFields::COLUMNS,
+        ..AnalyzeOptions::default()
+    };
+    let metadata = lialoonk_sql_query_parser::analyze_sql_opts(query, &options)?;
+    assert!(metadata.tables.is_empty());
+    assert!(metadata.aliases.is_empty());
+    assert!(metadata.where_predicates.is_empty());
+    assert!(metadata
+        .resolved_columns
+        .iter()
+        .any(|column| column.name == "total" && column.table.as_deref() == Some("orders")));
+
+    Ok(())
+}
+
+#[test]
+fn test_union_in_derived_table() -> Result<()> {
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "SELECT t.a FROM (SELECT a FROM x UNION SELECT a FROM y) t WHERE t.a > 1",
+    )?;
+
+    let reads = &metadata.derived_tables["t"];
+    assert_eq!(reads.len(), 2);
+    assert!(reads.contains("x"));
+    assert!(reads.contains("y"));
+    assert!(metadata.tables.contains("x"));
+    assert!(metadata.tables.contains("y"));
+
+    Ok(())
+}
+
+#[test]
+fn test_derived_aliases() -> Result<()> {
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "SELECT t.x, u.name FROM (SELECT x FROM events) AS t JOIN users u ON t.x = u.id",
+    )?;
+
+    assert!(metadata.derived_aliases.contains("t"));
+    assert!(!metadata.aliases.contains_key("t"));
+    assert_eq!(metadata.aliases["u"], "users");
+    assert!(!metadata.derived_aliases.contains("u"));
+
+    Ok(())
+}
+
+#[test]
+fn test_fingerprint_canonicalizes_in_lists() -> Result<()> {
+    let sorted =
+        lialoonk_sql_query_parser::fingerprint("SELECT id FROM users WHERE id IN (1, 2, 3)")?;
+    let shuffled = lialoonk_sql_query_parser::fingerprint(
+        "select id from users\nwhere id in (3,1,2,1) -- ids",
+    )?;
+    let different =
+        lialoonk_sql_query_parser::fingerprint("SELECT id FROM users WHERE id IN (1, 2, 4)")?;
+
+    assert_eq!(sorted, "SELECT id FROM users WHERE id IN (1, 2, 3)");
+    assert_eq!(shuffled, sorted);
+    assert_ne!(different, sorted);
+
+    let sanitized = lialoonk_sql_query_parser::sanitize_for_tracing(
+        "SELECT id FROM users WHERE id IN (3, 1, 2)",
+    )?;
+    assert_eq!(sanitized, "SELECT id FROM users WHERE id IN (?, ?, ?)");
+    assert_eq!(
+        lialoonk_sql_query_parser::sanitize_for_tracing(
+            "SELECT id FROM users WHERE id IN (1, 2, 4)"
+        )?,
+        sanitized
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_mixed_quote_styles() -> Result<()> {
+    assert!(lialoonk_sql_query_parser::mixed_quote_styles(
+        "SELECT \"id\", `name` FROM users"
+    )?);
+    assert!(lialoonk_sql_query_parser::mixed_quote_styles(
+        "SELECT u.[id] FROM \"users\" u"
+    )?);
+    assert!(!lialoonk_sql_query_parser::mixed_quote_styles(
+        "SELECT \"id\", \"name\" FROM \"users\""
+    )?);
+    assert!(!lialoonk_sql_query_parser::mixed_quote_styles(
+        "SELECT id, name FROM users"
+    )?);
+
+    Ok(())
+}
+
+#[test]
+fn test_metadata_json_round_trip() -> Result<()> {
+    let query = "SELECT name, COUNT(*) FROM users WHERE id IN (1, 2) GROUP BY name";
+    let metadata = lialoonk_sql_query_parser::analyze_sql(query)?;
+    let json = lialoonk_sql_query_parser::analyze_sql_json(query)?;
+    assert_eq!(
+        lialoonk_sql_query_parser::metadata_from_json(&json)?,
+        metadata
+    );
+
+    let mut value: serde_json::Value = serde_json::from_str(&json)?;
+    value
+        .as_object_mut()
+        .expect("metadata serializes as an object")
+        .remove("where_predicates");
+    let reloaded = lialoonk_sql_query_parser::metadata_from_json(&value.to_string())?;
+
+    assert!(reloaded.where_predicates.is_empty());
+    assert_eq!(reloaded.tables, metadata.tables);
+    assert_eq!(reloaded.aggregates, metadata.aggregates);
+
+    Ok(())
+}
+
+#[test]
+fn test_projected_column_count() -> Result<()> {
+    assert_eq!(
+        lialoonk_sql_query_parser::projected_column_count("SELECT a, b FROM t")?,
+        Some(2)
+    );
+    assert_eq!(
+        lialoonk_sql_query_parser::projected_column_count("SELECT * FROM t")?,
+        None
+    );
+    assert_eq!(
+        lialoonk_sql_query_parser::projected_column_count("SELECT a, t.* FROM t")?,
+        None
+    );
+    assert_eq!(
+        lialoonk_sql_query_parser::projected_column_count(
+            "SELECT a FROM t WHERE b IN (SELECT b, c FROM u)"
+        )?,
+        Some(1)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn implicit_joins_rejected_when_forbidden() -> Result<()> {
+    let options = AnalyzeOptions {
+        forbid_implicit_joins: true,
+        ..AnalyzeOptions::default()
+    };
+
+    let explicit = lialoonk_sql_query_parser::analyze_sql_opts(
+        "SELECT u.id FROM users u JOIN orders o ON u.id = o.user_id",
+        &options,
+    )?;
+    assert_eq!(explicit.joins.len(), 1);
+
+    let comma = "SELECT u.id FROM users u, orders o WHERE u.id = o.user_id";
+    let err = lialoonk_sql_query_parser::analyze_sql_opts(comma, &options).unwrap_err();
+    assert!(matches!(
+        err,
+        ParseError::Policy { ref span, .. } if &comma[span.clone()] == "orders o"
+    ));
+
+    let metadata = lialoonk_sql_query_parser::analyze_sql(comma)?;
+    assert!(metadata.tables.contains("users"));
+    assert!(metadata.tables.contains("orders"));
+
+    Ok(())
+}
+
+#[test]
+fn test_window_functions() -> Result<()> {
+    let metadata =
+        lialoonk_sql_query_parser::analyze_sql("SELECT id, COUNT(*) OVER () FROM orders")?;
+
+    assert_eq!(metadata.window_functions.len(), 1);
+    let window = &metadata.window_functions[0];
+    assert_eq!(window.function, "COUNT");
+    assert!(window.partition_by.is_empty());
+    assert!(window.order_by.is_empty());
+    assert!(metadata.windowed_aggregates.contains("COUNT"));
+
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "SELECT SUM(amount) OVER (PARTITION BY customer_id ORDER BY created_at DESC) FROM orders",
+    )?;
+    let window = &metadata.window_functions[0];
+    assert_eq!(window.partition_by, vec!["customer_id".to_string()]);
+    assert_eq!(window.order_by, vec!["created_at DESC".to_string()]);
+    assert!(metadata.columns.contains("customer_id"));
+    assert!(metadata.columns.contains("created_at"));
+    assert_eq!(window.frame, None);
+
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "SELECT SUM(amount) OVER (PARTITION BY customer_id ORDER BY created_at \
+         rows between unbounded preceding and current row), \
+         AVG(amount) OVER (ORDER BY created_at RANGE 3 PRECEDING) FROM orders",
+    )?;
+    assert_eq!(metadata.window_functions.len(), 2);
+    assert_eq!(
+        metadata.window_functions[0].frame.as_deref(),
+        Some("ROWS BETWEEN UNBOUNDED PRECEDING AND CURRENT ROW")
+    );
+    assert_eq!(
+        metadata.window_functions[0].order_by,
+        vec!["created_at".to_string()]
+    );
+    assert_eq!(
+        metadata.window_functions[1].frame.as_deref(),
+        Some("RANGE 3 PRECEDING")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_filter_with_window() -> Result<()> {
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "SELECT SUM(amount) FILTER (WHERE status = 'paid') OVER (PARTITION BY region) FROM orders",
+    )?;
+
+    assert!(metadata.filtered_aggregates.contains("SUM"));
+    assert!(metadata.windowed_aggregates.contains("SUM"));
+    assert_eq!(
+        metadata.window_functions[0].partition_by,
+        vec!["region".to_string()]
+    );
+    assert!(metadata.columns.contains("status"));
+    assert!(metadata.columns.contains("region"));
+
+    assert!(lialoonk_sql_query_parser::analyze_sql(
+        "SELECT SUM(amount) OVER (PARTITION BY region) FILTER (WHERE paid) FROM orders"
+    )
+    .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_tables_match() -> Result<()> {
+    let unquoted = lialoonk_sql_query_parser::analyze_sql("SELECT id FROM Users")?;
+    assert!(lialoonk_sql_query_parser::tables_match(
+        &unquoted,
+        "users",
+        Dialect::Postgres
+    ));
+    assert!(!lialoonk_sql_query_parser::tables_match(
+        &unquoted,
+        "users",
+        Dialect::MySql
+    ));
+
+    let quoted = lialoonk_sql_query_parser::analyze_sql("SELECT id FROM \"Users\"")?;
+    assert!(!lialoonk_sql_query_parser::tables_match(
+        &quoted,
+        "users",
+        Dialect::Postgres
+    ));
+    assert!(lialoonk_sql_query_parser::tables_match(
+        &quoted,
+        "\"Users\"",
+        Dialect::Postgres
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_distinct_aggregate_with_filter() -> Result<()> {
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "SELECT COUNT(DISTINCT customer_id) FILTER (WHERE status = 'paid') FROM orders",
+    )?;
+
+    assert!(metadata.distinct_aggregates.contains("COUNT"));
+    assert!(metadata.filtered_aggregates.contains("COUNT"));
+    assert!(metadata.columns.contains("customer_id"));
+    assert!(metadata.columns.contains("status"));
+
+    Ok(())
+}
+
+#[test]
+fn test_join_types_and_on_columns() -> Result<()> {
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "SELECT u.id FROM users u LEFT OUTER JOIN orders o ON u.id = o.user_id \
+         CROSS JOIN regions r",
+    )?;
+
+    assert_eq!(metadata.joins.len(), 2);
+    assert_eq!(metadata.joins[0].join_type.as_deref(), Some("LEFT OUTER"));
+    assert_eq!(
+        metadata.joins[0].on_columns,
+        vec![("u.id".to_string(), "o.user_id".to_string())]
+    );
+    assert_eq!(metadata.joins[1].join_type.as_deref(), Some("CROSS"));
+    assert!(metadata.joins[1].on_columns.is_empty());
+    assert_eq!(metadata.aliases.get("u").map(String::as_str), Some("users"));
+
+    Ok(())
+}
+
+#[test]
+fn test_fanout_risk() -> Result<()> {
+    let equi = lialoonk_sql_query_parser::analyze_sql(
+        "SELECT a.id FROM a JOIN b ON a.id = b.a_id JOIN c ON b.id = c.b_id",
+    )?;
+    let unconditioned = lialoonk_sql_query_parser::analyze_sql(
+        "SELECT a.id FROM a CROSS JOIN b LEFT JOIN c ON c.ts > a.ts",
+    )?;
+
+    assert_eq!(lialoonk_sql_query_parser::fanout_risk(&equi), 0);
+    assert!(
+        lialoonk_sql_query_parser::fanout_risk(&unconditioned)
+            > lialoonk_sql_query_parser::fanout_risk(&equi)
+    );
+    assert_eq!(lialoonk_sql_query_parser::fanout_risk(&unconditioned), 5);
+
+    Ok(())
+}
+
+#[test]
+fn test_window_projection_columns() -> Result<()> {
+    let columns = lialoonk_sql_query_parser::window_projection_columns(
+        "SELECT id, ROW_NUMBER() OVER (PARTITION BY a ORDER BY b DESC) FROM t ORDER BY c",
+    )?;
+    let expected: HashSet<String> = ["a", "b"].iter().map(|c| c.to_string()).collect();
+    assert_eq!(columns, expected);
+
+    assert!(lialoonk_sql_query_parser::window_projection_columns("SELECT a FROM t")?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_shadowing_aliases() -> Result<()> {
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "SELECT o.id FROM orders o JOIN customers orders ON o.customer_id = orders.id",
+    )?;
+    assert_eq!(
+        lialoonk_sql_query_parser::shadowing_aliases(&metadata),
+        vec!["orders".to_string()]
+    );
+
+    let metadata = lialoonk_sql_query_parser::analyze_sql("SELECT u.id FROM users u")?;
+    assert!(lialoonk_sql_query_parser::shadowing_aliases(&metadata).is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_order_by_items() -> Result<()> {
+    let metadata = lialoonk_sql_query_parser::analyze_sql_with_dialect(
+        "SELECT id, name FROM users ORDER BY name USING <, id desc",
+        Dialect::Postgres,
+    )?;
+
+    assert_eq!(
+        metadata.order_by,
+        vec![
+            OrderByItem {
+                expr: "name".to_string(),
+                direction: None,
+                using_operator: Some("<".to_string()),
+            },
+            OrderByItem {
+                expr: "id".to_string(),
+                direction: Some("DESC".to_string()),
+                using_operator: None,
+            },
+        ]
+    );
+
+    let query = "SELECT id FROM users ORDER BY id USING >";
+    assert!(lialoonk_sql_query_parser::analyze_sql(query).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_write_analysis_json() -> Result<()> {
+    let query = "SELECT u.name FROM users u WHERE u.id = 1";
+    let mut buffer = Vec::new();
+    lialoonk_sql_query_parser::write_analysis_json(query, &mut buffer)?;
+
+    let metadata = lialoonk_sql_query_parser::metadata_from_json(std::str::from_utf8(&buffer)?)?;
+    assert_eq!(metadata, lialoonk_sql_query_parser::analyze_sql(query)?);
+
+    Ok(())
+}
+
+#[test]
+fn test_strict_mode_check() -> Result<()> {
+    assert_eq!(
+        lialoonk_sql_query_parser::strict_mode_check(
+            "SELECT dept, UPPER(name) FROM emp GROUP BY dept"
+        )?,
+        vec!["name".to_string()]
+    );
+    assert!(lialoonk_sql_query_parser::strict_mode_check(
+        "SELECT dept, UPPER(dept), COUNT(name) FROM emp GROUP BY dept"
+    )?
+    .is_empty());
+    assert_eq!(
+        lialoonk_sql_query_parser::strict_mode_check("SELECT name, COUNT(*) FROM emp")?,
+        vec!["name".to_string()]
+    );
+    assert!(lialoonk_sql_query_parser::strict_mode_check("SELECT name FROM emp")?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_create_table_options() -> Result<()> {
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "CREATE TABLE events (id INTEGER PRIMARY KEY, name VARCHAR(64) NOT NULL) TABLESPACE fast_ssd",
+    )?;
+    assert!(metadata.tables.contains("events"));
+    assert_eq!(
+        metadata.table_options,
+        vec!["TABLESPACE fast_ssd".to_string()]
+    );
+
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "CREATE TABLE logs (id INTEGER) WITH (fillfactor = 70, autovacuum_enabled = false) \
+         INHERITS (base_logs)",
+    )?;
+    assert_eq!(
+        metadata.table_options,
+        vec![
+            "WITH (fillfactor = 70, autovacuum_enabled = false)".to_string(),
+            "INHERITS (base_logs)".to_string(),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_create_table_definition() -> Result<()> {
+    use lialoonk_sql_query_parser::{ColumnDefinition, ForeignKey};
+
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "CREATE TABLE orders (id INTEGER, user_id INTEGER NOT NULL REFERENCES users (id), \
+         code VARCHAR(16) UNIQUE, status TEXT DEFAULT 'new', \
+         PRIMARY KEY (id), CONSTRAINT fk_coupon FOREIGN KEY (code) REFERENCES coupons)",
+    )?;
+    let table = metadata
+        .table_definition
+        .expect("CREATE TABLE has a definition");
+
+    assert_eq!(table.name, "orders");
+    assert_eq!(
+        table.columns[0],
+        ColumnDefinition {
+            name: "id".to_string(),
+            data_type: "INTEGER".to_string(),
+            nullable: false,
+            default: None,
+        }
+    );
+    assert!(!table.columns[1].nullable);
+    assert_eq!(table.columns[2].data_type, "VARCHAR(16)");
+    assert!(table.columns[2].nullable);
+    assert_eq!(table.columns[3].default.as_deref(), Some("'new'"));
+    assert_eq!(table.primary_key, vec!["id".to_string()]);
+    assert_eq!(table.unique, vec![vec!["code".to_string()]]);
+    assert_eq!(
+        table.foreign_keys,
+        vec![
+            ForeignKey {
+                columns: vec!["user_id".to_string()],
+                referenced_table: "users".to_string(),
+                referenced_columns: vec!["id".to_string()],
+            },
+            ForeignKey {
+                columns: vec!["code".to_string()],
+                referenced_table: "coupons".to_string(),
+                referenced_columns: Vec::new(),
+            },
+        ]
+    );
+
+    let statement =
+        lialoonk_sql_query_parser::parse_ast("CREATE TABLE t (id INTEGER PRIMARY KEY)")?;
+    assert!(matches!(
+        statement,
+        lialoonk_sql_query_parser::ast::Statement::CreateTable(table)
+            if table.primary_key == vec!["id".to_string()]
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_schema_migration_statements() -> Result<()> {
+    use lialoonk_sql_query_parser::QueryKind;
+
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "ALTER TABLE users ADD COLUMN email VARCHAR(255) NOT NULL",
+    )?;
+    assert_eq!(metadata.kind, QueryKind::AlterTable);
+    assert!(metadata.tables.contains("users"));
+    assert!(metadata.columns.contains("email"));
+
+    let metadata =
+        lialoonk_sql_query_parser::analyze_sql("alter table users rename column email to mail")?;
+    assert_eq!(metadata.columns.len(), 2);
+    assert!(metadata.columns.contains("mail"));
+
+    let metadata =
+        lialoonk_sql_query_parser::analyze_sql("ALTER TABLE users DROP COLUMN IF EXISTS email")?;
+    assert!(metadata.columns.contains("email"));
+
+    let metadata = lialoonk_sql_query_parser::analyze_sql("DROP TABLE IF EXISTS users, orders;")?;
+    assert_eq!(metadata.kind, QueryKind::DropTable);
+    assert_eq!(metadata.tables.len(), 2);
+
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "CREATE UNIQUE INDEX idx_users_email ON users (email, tenant_id)",
+    )?;
+    assert_eq!(metadata.kind, QueryKind::CreateIndex);
+    assert!(metadata.indexes.contains("idx_users_email"));
+    assert!(metadata.tables.contains("users"));
+    assert!(!metadata.tables.contains("idx_users_email"));
+    assert!(metadata.columns.contains("tenant_id"));
+
+    let metadata = lialoonk_sql_query_parser::analyze_sql("DROP INDEX IF EXISTS idx_users_email")?;
+    assert_eq!(metadata.kind, QueryKind::DropIndex);
+    assert!(metadata.indexes.contains("idx_users_email"));
+    assert!(metadata.tables.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_driving_table() -> Result<()> {
+    assert_eq!(
+        lialoonk_sql_query_parser::driving_table(
+            "SELECT o.id FROM orders o JOIN customers c ON o.customer_id = c.id"
+        )?,
+        Some("orders".to_string())
+    );
+    assert_eq!(lialoonk_sql_query_parser::driving_table("SELECT 1")?, None);
+
+    Ok(())
+}
+
+#[test]
+fn test_cte_shadows_base_table() -> Result<()> {
+    let query = "WITH RECURSIVE users AS (SELECT id, manager_id FROM staff \
+                 UNION ALL SELECT s.id, s.manager_id FROM staff s, users u WHERE s.manager_id = u.id) \
+                 SELECT * FROM users";
+    let metadata = lialoonk_sql_query_parser::analyze_sql(query)?;
+
+    assert!(metadata.cte_names.contains("users"));
+    assert!(!metadata.tables.contains("users"));
+    assert!(metadata.tables.contains("staff"));
+
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "WITH recent AS (SELECT id FROM orders WHERE id > 10) SELECT id FROM recent",
+    )?;
+    assert!(metadata.cte_names.contains("recent"));
+    assert!(!metadata.tables.contains("recent"));
+    assert!(metadata.tables.contains("orders"));
+
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "WITH Recent AS (SELECT * FROM orders WHERE ts > now()) \
+         SELECT * FROM users WHERE id IN (SELECT user_id FROM RECENT)",
+    )?;
+    assert_eq!(metadata.tables.len(), 2);
+    assert!(metadata.tables.contains("users"));
+    assert!(metadata.tables.contains("orders"));
+
+    Ok(())
+}
+
+#[test]
+fn test_qualified_star_columns() -> Result<()> {
+    let metadata =
+        lialoonk_sql_query_parser::analyze_sql("SELECT u.*, o.total FROM users u, orders o")?;
+
+    assert_eq!(
+        metadata.qualified_columns,
+        vec![
+            QualifiedColumn {
+                table: Some("users".to_string()),
+                column: "*".to_string(),
+            },
+            QualifiedColumn {
+                table: Some("orders".to_string()),
+                column: "total".to_string(),
+            },
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_mysql_index_hints() -> Result<()> {
+    let query = "SELECT name FROM users USE INDEX (idx_name) WHERE id = 1";
+    let metadata = lialoonk_sql_query_parser::analyze_sql_with_dialect(query, Dialect::MySql)?;
+
+    assert_eq!(
+        metadata.index_hints,
+        vec![("users".to_string(), "USE INDEX (idx_name)".to_string())]
+    );
+    assert!(!metadata.columns.contains("idx_name"));
+    assert!(metadata.columns.contains("id"));
+
+    let query = "SELECT o.id FROM orders o FORCE INDEX (idx_created, idx_status)";
+    let metadata = lialoonk_sql_query_parser::analyze_sql_with_dialect(query, Dialect::MySql)?;
+
+    assert_eq!(metadata.aliases["o"], "orders");
+    assert_eq!(
+        metadata.index_hints,
+        vec![(
+            "orders".to_string(),
+            "FORCE INDEX (idx_created, idx_status)".to_string()
+        )]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn index_hints_require_mysql_dialect() {
+    let query = "SELECT name FROM users USE INDEX (idx_name)";
+    assert!(lialoonk_sql_query_parser::analyze_sql(query).is_err());
+    assert!(lialoonk_sql_query_parser::analyze_sql_with_dialect(query, Dialect::Postgres).is_err());
+}
+
+#[test]
+fn test_correlation_pairs() -> Result<()> {
+    let query = "SELECT id FROM a WHERE EXISTS (SELECT 1 FROM b WHERE b.a = a.id)";
+    assert_eq!(
+        lialoonk_sql_query_parser::correlation_pairs(query)?,
+        vec![("a.id".to_string(), "b.a".to_string())]
+    );
+
+    let query = "SELECT u.id FROM users u WHERE EXISTS \
+                 (SELECT 1 FROM orders o WHERE u.id = o.user_id AND o.total > 10)";
+    assert_eq!(
+        lialoonk_sql_query_parser::correlation_pairs(query)?,
+        vec![("u.id".to_string(), "o.user_id".to_string())]
+    );
+
+    let query = "SELECT id FROM a WHERE id IN (SELECT a_id FROM b WHERE b.kind = 'x')";
+    assert!(lialoonk_sql_query_parser::correlation_pairs(query)?.is_empty());
+
+    Ok(())
+}
+
+#[cfg(feature = "diagnostics")]
+#[test]
+fn test_render_diagnostic() {
+    let query = "SELECT id FROM users WHERE name = 'oops";
+    let error = lialoonk_sql_query_parser::parse_sql(query).unwrap_err();
+    let rendered = lialoonk_sql_query_parser::render_diagnostic(query, &error.into());
+
+    assert!(rendered.contains("'oops"));
+    assert!(rendered.contains('^'));
+}
+
 #[test]
 fn all_grammar_rules_test() -> Result<()> {
     let cases = [
@@ -92,13 +940,35 @@ fn all_grammar_rules_test() -> Result<()> {
         (Rule::COMMENT, "-- demo\n"),
         (Rule::sql, "SELECT id FROM users"),
         (Rule::statement, "SELECT id FROM users"),
+        (
+            Rule::script,
+            "SET search_path = public; SELECT id FROM users;",
+        ),
+        (Rule::script_statement, "SELECT id FROM users"),
         (
             Rule::compound_select,
             "SELECT id FROM users UNION SELECT id FROM posts",
         ),
         (Rule::union_clause, "UNION SELECT id FROM users"),
+        (
+            Rule::intersect_select,
+            "SELECT id FROM users INTERSECT SELECT id FROM posts",
+        ),
+        (Rule::intersect_clause, "INTERSECT ALL SELECT id FROM users"),
+        (
+            Rule::with_clause,
+            "WITH RECURSIVE t (n) AS (SELECT 1 UNION ALL SELECT n FROM t)",
+        ),
+        (Rule::cte, "recent AS (SELECT id FROM orders)"),
+        (Rule::recursive, "RECURSIVE"),
         (Rule::select_stmt, "SELECT id FROM users WHERE id = 1"),
         (Rule::insert_stmt, "INSERT INTO users VALUES (1)"),
+        (Rule::insert_or, "OR REPLACE"),
+        (Rule::conflict_action, "IGNORE"),
+        (Rule::set_session_stmt, "SET TIME ZONE 'UTC'"),
+        (Rule::set_value, "public"),
+        (Rule::tx_stmt, "ROLLBACK TO SAVEPOINT before_update"),
+        (Rule::where_current_of, "WHERE CURRENT OF my_cursor"),
         (
             Rule::update_stmt,
             "UPDATE users SET name = 'John' WHERE id = 1",
@@ -112,9 +982,60 @@ fn all_grammar_rules_test() -> Result<()> {
         (Rule::distinct, "DISTINCT"),
         (Rule::projection, "*"),
         (Rule::projection_list, "id, name"),
+        (Rule::qualified_wildcard, "users.*"),
+        (
+            Rule::create_table_stmt,
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL)",
+        ),
+        (
+            Rule::create_view_stmt,
+            "CREATE VIEW active_users AS SELECT id FROM users WHERE active = TRUE",
+        ),
+        (Rule::materialized, "MATERIALIZED"),
+        (Rule::check_option, "WITH LOCAL CHECK OPTION"),
+        (Rule::check_option_level, "CASCADED"),
+        (Rule::column_definition, "name VARCHAR(255) DEFAULT 'x'"),
+        (
+            Rule::table_constraint,
+            "CONSTRAINT fk_user FOREIGN KEY (user_id) REFERENCES users (id)",
+        ),
+        (Rule::references_clause, "REFERENCES users (id)"),
+        (
+            Rule::alter_table_stmt,
+            "ALTER TABLE users ADD COLUMN email TEXT NOT NULL",
+        ),
+        (Rule::alter_add_column, "ADD email TEXT"),
+        (Rule::alter_drop_column, "DROP COLUMN IF EXISTS email"),
+        (Rule::alter_rename_column, "RENAME COLUMN email TO mail"),
+        (Rule::drop_table_stmt, "DROP TABLE IF EXISTS users, orders"),
+        (
+            Rule::create_index_stmt,
+            "CREATE UNIQUE INDEX idx_email ON users (email)",
+        ),
+        (Rule::unique_index, "UNIQUE"),
+        (Rule::drop_index_stmt, "DROP INDEX idx_email ON users"),
+        (Rule::if_exists, "IF EXISTS"),
+        (Rule::type_name, "NUMERIC(10, 2)"),
+        (Rule::column_constraint, "NOT NULL"),
+        (Rule::table_option, "TABLESPACE pg_default"),
+        (Rule::storage_parameter, "fillfactor = 70"),
+        (Rule::sort_direction, "DESC"),
+        (Rule::sort_operator, "<"),
+        (Rule::window_spec, "(PARTITION BY a ORDER BY b)"),
+        (
+            Rule::window_frame,
+            "ROWS BETWEEN UNBOUNDED PRECEDING AND CURRENT ROW",
+        ),
+        (Rule::frame_units, "RANGE"),
+        (Rule::frame_bound, "3 FOLLOWING"),
+        (Rule::filter_clause, "FILTER (WHERE paid)"),
+        (Rule::cast_suffix, "::text[]"),
+        (Rule::cast_expr, "CAST(price AS DECIMAL(10,2))"),
+        (Rule::partition_by_clause, "PARTITION BY a, b"),
         (Rule::projection_item, "COUNT(id) AS total"),
         (Rule::from_item, "users u"),
         (Rule::table_factor, "users AS u"),
+        (Rule::index_hint, "IGNORE INDEX (idx_a, idx_b)"),
         (
             Rule::join_clause,
             "JOIN posts p ON u.id = p.user_id AND p.user_id = u.id",
@@ -124,6 +1045,10 @@ fn all_grammar_rules_test() -> Result<()> {
         (Rule::having_clause, "HAVING COUNT(id) > 1"),
         (Rule::order_by_clause, "ORDER BY id DESC, name"),
         (Rule::limit_clause, "LIMIT 10"),
+        (Rule::offset_clause, "OFFSET 20 ROWS"),
+        (Rule::limit_clause, "LIMIT :page_size"),
+        (Rule::offset_clause, "OFFSET ?"),
+        (Rule::fetch_clause, "FETCH NEXT 10 ROWS ONLY"),
         (Rule::order_list, "id DESC, name"),
         (Rule::order_item, "id DESC"),
         (Rule::identifier_list, "id, name, age"),
@@ -134,13 +1059,36 @@ fn all_grammar_rules_test() -> Result<()> {
         (Rule::not_expr, "NOT id = 1"),
         (Rule::comparison, "id = 1"),
         (Rule::comparison_suffix, "= 1"),
+        (Rule::comparison_suffix, "IS NOT TRUE"),
+        (Rule::comparison_suffix, "IS DISTINCT FROM b"),
         (Rule::in_rhs, "1, 2"),
         (Rule::comp_op, "="),
+        (Rule::regex_op, "!~*"),
+        (Rule::null_safe_eq, "<=>"),
+        (Rule::concat_op, "||"),
         (Rule::addition, "1 + 2 - 3"),
         (Rule::multiplication, "1 * 2 / 3"),
+        (Rule::multiplication, "7 % 4"),
         (Rule::unary, "-id"),
         (Rule::primary, "(1)"),
         (Rule::function_call, "func(1, 2)"),
+        (Rule::function_name, "pg_catalog.upper"),
+        (Rule::exists_expr, "EXISTS (SELECT 1 FROM users)"),
+        (Rule::not_expr, "NOT EXISTS (SELECT 1 FROM users)"),
+        (Rule::subquery_expr, "(SELECT MAX(id) FROM users)"),
+        (
+            Rule::case_expr,
+            "CASE WHEN status = 1 THEN 'active' ELSE 'inactive' END",
+        ),
+        (Rule::case_operand, "status"),
+        (Rule::when_keyword, "WHEN"),
+        (Rule::when_clause, "WHEN 1 THEN 'active'"),
+        (Rule::else_clause, "ELSE 'inactive'"),
+        (Rule::parameter, "$1"),
+        (Rule::anonymous_parameter, "?"),
+        (Rule::positional_parameter, "$2"),
+        (Rule::named_parameter, ":name"),
+        (Rule::niladic_function, "CURRENT_TIMESTAMP"),
         (Rule::column, "users.id"),
         (Rule::literal, "'abc'"),
         (Rule::boolean, "TRUE"),
@@ -148,6 +1096,8 @@ fn all_grammar_rules_test() -> Result<()> {
         (Rule::string, "'abc'"),
         (Rule::alias, "alias_name"),
         (Rule::identifier, "table_name"),
+        (Rule::quoted_identifier, "\"table name\""),
+        (Rule::quoted_identifier, "[odd]]name]"),
         (Rule::SELECT_KEY, "SELECT"),
         (Rule::FROM_KEY, "FROM"),
         (Rule::WHERE_KEY, "WHERE"),
@@ -169,6 +1119,8 @@ fn all_grammar_rules_test() -> Result<()> {
         (Rule::DESC_KEY, "DESC"),
         (Rule::AND_KEY, "AND"),
         (Rule::OR_KEY, "OR"),
+        (Rule::and_keyword, "AND"),
+        (Rule::or_keyword, "OR"),
         (Rule::NOT_KEY, "NOT"),
         (Rule::LIKE_KEY, "LIKE"),
         (Rule::TRUE_KEY, "TRUE"),
@@ -185,45 +1137,2032 @@ fn all_grammar_rules_test() -> Result<()> {
         (Rule::BETWEEN_KEY, "BETWEEN"),
         (Rule::IN_KEY, "IN"),
         (Rule::IS_KEY, "IS"),
+        (Rule::EXISTS_KEY, "EXISTS"),
         (Rule::JOIN_TYPE, "LEFT OUTER"),
+        (Rule::CROSS_KEY, "CROSS"),
+        (Rule::REPLACE_KEY, "REPLACE"),
+        (Rule::ABORT_KEY, "ABORT"),
+        (Rule::FAIL_KEY, "FAIL"),
+        (Rule::VIEW_KEY, "VIEW"),
+        (Rule::CHECK_KEY, "CHECK"),
+        (Rule::OPTION_KEY, "OPTION"),
+        (Rule::CASCADED_KEY, "CASCADED"),
+        (Rule::SIMILAR_KEY, "SIMILAR"),
+        (Rule::FOR_KEY, "FOR"),
+        (Rule::RETURNING_KEY, "RETURNING"),
+        (Rule::RANGE_KEY, "RANGE"),
+        (Rule::GROUPS_KEY, "GROUPS"),
+        (Rule::UNBOUNDED_KEY, "UNBOUNDED"),
+        (Rule::PRECEDING_KEY, "PRECEDING"),
+        (Rule::FOLLOWING_KEY, "FOLLOWING"),
+        (Rule::CASE_KEY, "CASE"),
+        (Rule::WHEN_KEY, "WHEN"),
+        (Rule::THEN_KEY, "THEN"),
+        (Rule::ELSE_KEY, "ELSE"),
+        (Rule::END_KEY, "END"),
+        (Rule::UNIQUE_KEY, "UNIQUE"),
+        (Rule::FOREIGN_KEY, "FOREIGN"),
+        (Rule::REFERENCES_KEY, "REFERENCES"),
+        (Rule::CONSTRAINT_KEY, "CONSTRAINT"),
+        (Rule::ALTER_KEY, "ALTER"),
+        (Rule::ADD_KEY, "ADD"),
+        (Rule::DROP_KEY, "DROP"),
+        (Rule::RENAME_KEY, "RENAME"),
+        (Rule::COLUMN_KEY, "COLUMN"),
+        (Rule::IF_KEY, "IF"),
+        (Rule::MATERIALIZED_KEY, "MATERIALIZED"),
+        (Rule::TOP_KEY, "TOP"),
+        (Rule::CAST_KEY, "CAST"),
         (Rule::OUTER_KEY, "OUTER"),
+        (Rule::USE_KEY, "USE"),
+        (Rule::FORCE_KEY, "FORCE"),
+        (Rule::IGNORE_KEY, "IGNORE"),
+        (Rule::INDEX_KEY, "INDEX"),
+        (Rule::OFFSET_KEY, "OFFSET"),
+        (Rule::FETCH_KEY, "FETCH"),
+        (Rule::FIRST_KEY, "FIRST"),
+        (Rule::NEXT_KEY, "NEXT"),
+        (Rule::ROW_KEY, "ROW"),
+        (Rule::ROWS_KEY, "ROWS"),
+        (Rule::ONLY_KEY, "ONLY"),
+        (Rule::CURRENT_DATE_KEY, "CURRENT_DATE"),
+        (Rule::CURRENT_TIME_KEY, "CURRENT_TIME"),
+        (Rule::CURRENT_TIMESTAMP_KEY, "CURRENT_TIMESTAMP"),
+        (Rule::CURRENT_USER_KEY, "CURRENT_USER"),
+        (Rule::OVER_KEY, "OVER"),
+        (Rule::PARTITION_KEY, "PARTITION"),
+        (Rule::CREATE_KEY, "CREATE"),
+        (Rule::TABLE_KEY, "TABLE"),
+        (Rule::PRIMARY_KEY, "PRIMARY"),
+        (Rule::KEY_KEY, "KEY"),
+        (Rule::DEFAULT_KEY, "DEFAULT"),
+        (Rule::TABLESPACE_KEY, "TABLESPACE"),
+        (Rule::WITH_KEY, "WITH"),
+        (Rule::INHERITS_KEY, "INHERITS"),
+        (Rule::RECURSIVE_KEY, "RECURSIVE"),
+        (Rule::SESSION_KEY, "SESSION"),
+        (Rule::LOCAL_KEY, "LOCAL"),
+        (Rule::TIME_KEY, "TIME"),
+        (Rule::ZONE_KEY, "ZONE"),
+        (Rule::TO_KEY, "TO"),
+        (Rule::BEGIN_KEY, "BEGIN"),
+        (Rule::COMMIT_KEY, "COMMIT"),
+        (Rule::ROLLBACK_KEY, "ROLLBACK"),
+        (Rule::TRANSACTION_KEY, "TRANSACTION"),
+        (Rule::SAVEPOINT_KEY, "SAVEPOINT"),
+        (Rule::FILTER_KEY, "FILTER"),
+        (Rule::EXCEPT_KEY, "EXCEPT"),
+        (Rule::INTERSECT_KEY, "INTERSECT"),
+        (Rule::CURRENT_KEY, "CURRENT"),
+        (Rule::OF_KEY, "OF"),
+        (Rule::IDENT_CHAR, "_"),
         (Rule::SPACE, " "),
         (Rule::RESERVED_KEYWORD, "SELECT"),
         (Rule::alias_identifier, "users"),
+        (Rule::qualified_identifier, "analytics.public.orders"),
+        (Rule::top_clause, "TOP (10)"),
+        (Rule::top_keyword, "TOP"),
+        (Rule::set_scope, "SESSION"),
+        (Rule::time_zone_keyword, "TIME ZONE"),
+    ];
+
+    for (rule, input) in cases {
+        assert_rule(rule, input)?;
+    }
+
+    Ok(())
+}
+
+#[test]
+fn select_stmt_with_join_and_filters() -> Result<()> {
+    assert_rule(
+        Rule::select_stmt,
+        "SELECT DISTINCT u.id, name \
+         FROM users u JOIN posts p ON u.id = p.user_id \
+         WHERE p.kind = 'blog' \
+         GROUP BY u.id, name \
+         HAVING COUNT(p.id) > 1 \
+         ORDER BY u.id DESC \
+         LIMIT 10",
+    )
+}
+
+#[test]
+fn insert_update_delete_statements() -> Result<()> {
+    assert_rule(Rule::insert_stmt, "INSERT INTO metrics VALUES (sum(value))")?;
+    assert_rule(
+        Rule::update_stmt,
+        "UPDATE users SET name = 'Alice', age = 42 WHERE id = 10",
+    )?;
+    assert_rule(
+        Rule::delete_stmt,
+        "DELETE FROM audit_logs WHERE created_at < '2024-01-01'",
+    )?;
+    Ok(())
+}
+
+#[test]
+fn order_by_after_filter_is_not_read_as_or() -> Result<()> {
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "SELECT id FROM users WHERE active = 1 ORDER BY id",
+    )?;
+
+    assert!(!metadata.columns.contains("DER"));
+    assert_eq!(metadata.where_predicates.len(), 1);
+
+    assert!(lialoonk_sql_query_parser::analyze_sql("SELECT id FROM t WHERE a = 1 ORx").is_err());
+    assert!(lialoonk_sql_query_parser::analyze_sql("SELECT id FROM t WHERE a = 1 ANDx").is_err());
+    let metadata =
+        lialoonk_sql_query_parser::analyze_sql("SELECT id FROM t WHERE a = 1 OR(b = 2)AND(c = 3)")?;
+    assert_eq!(metadata.where_predicates.len(), 3);
+
+    Ok(())
+}
+
+#[test]
+fn test_set_session_statements() -> Result<()> {
+    let statements = lialoonk_sql_query_parser::analyze_statements(
+        "SET search_path = x;\nSET TIME ZONE 'UTC';\nSELECT id FROM users;",
+    )?;
+    assert_eq!(statements.len(), 3);
+
+    for set in &statements[..2] {
+        assert_eq!(set.kind, QueryKind::Set);
+        assert!(set.tables.is_empty());
+    }
+    assert_eq!(statements[2].kind, QueryKind::Select);
+    assert!(statements[2].tables.contains("users"));
+
+    assert!(lialoonk_sql_query_parser::analyze_statements("SELECT 1 SELECT 2").is_err());
+
+    for (sql, name) in [
+        (
+            "SET session_replication_role = replica",
+            "session_replication_role",
+        ),
+        ("SET local_threshold TO 5", "local_threshold"),
+        (
+            "SET timezone_abbreviations = 'Default'",
+            "timezone_abbreviations",
+        ),
+        (
+            "SET SESSION session_replication_role = replica",
+            "session_replication_role",
+        ),
+    ] {
+        let stmt = lialoonk_sql_query_parser::parse_sql(sql)?
+            .flatten()
+            .find(|pair| pair.as_rule() == Rule::set_session_stmt)
+            .expect("SET statement");
+        let variable = stmt
+            .into_inner()
+            .find(|pair| pair.as_rule() == Rule::identifier)
+            .expect("variable name");
+        assert_eq!(variable.as_str().trim_end(), name, "{sql}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_statement_spans() -> Result<()> {
+    let script = "SELECT id FROM users;\n  DELETE FROM orders WHERE id = 1 ;";
+    let statements = lialoonk_sql_query_parser::analyze_statements(script)?;
+
+    let spans: Vec<(usize, usize)> = statements
+        .iter()
+        .map(|meta| meta.statement_span)
+        .collect::<Option<_>>()
+        .context("missing statement span")?;
+    assert_eq!(&script[spans[0].0..spans[0].1], "SELECT id FROM users");
+    assert_eq!(
+        &script[spans[1].0..spans[1].1],
+        "DELETE FROM orders WHERE id = 1"
+    );
+    assert!(spans[0].1 <= spans[1].0);
+
+    assert_eq!(
+        lialoonk_sql_query_parser::analyze_sql("SELECT 1")?.statement_span,
+        None
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_transaction_statements() -> Result<()> {
+    let statements = lialoonk_sql_query_parser::analyze_statements(
+        "BEGIN;\nUPDATE accounts SET balance = 0 WHERE id = 1;\nROLLBACK TO sp1;\nCOMMIT;",
+    )?;
+    let kinds: Vec<QueryKind> = statements.iter().map(|meta| meta.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            QueryKind::Transaction,
+            QueryKind::Update,
+            QueryKind::Transaction,
+            QueryKind::Transaction
+        ]
+    );
+    assert!(statements[0].tables.is_empty());
+    assert!(statements[2].tables.is_empty());
+    assert!(statements[3].tables.is_empty());
+
+    let begin = lialoonk_sql_query_parser::analyze_sql("BEGIN TRANSACTION")?;
+    assert_eq!(begin.kind, QueryKind::Transaction);
+
+    Ok(())
+}
+
+#[test]
+fn test_script_iteration() -> Result<()> {
+    let mut tables = Vec::new();
+    for statement in Script::parse("SELECT id FROM users; DELETE FROM orders WHERE id = 1;") {
+        let meta = statement?;
+        tables.extend(meta.tables);
+    }
+    assert_eq!(tables, vec!["users".to_string(), "orders".to_string()]);
+
+    let results: Vec<_> = Script::parse("DELETE users").into_iter().collect();
+    assert_eq!(results.len(), 1);
+    assert!(matches!(results[0], Err(ParseError::Syntax(_))));
+
+    Ok(())
+}
+
+#[test]
+fn test_output_columns() -> Result<()> {
+    let columns = lialoonk_sql_query_parser::output_columns(
+        "SELECT u.id, COUNT(*) AS total, price * 2, u.* FROM users u",
+    )?;
+    let names: Vec<Option<&str>> = columns
+        .iter()
+        .map(|column| column.name.as_deref())
+        .collect();
+    assert_eq!(names, vec![Some("id"), Some("total"), None, None]);
+    assert_eq!(
+        columns[1],
+        OutputColumn {
+            name: Some("total".to_string()),
+            expr: "COUNT(*)".to_string(),
+            alias: Some("total".to_string()),
+        }
+    );
+    assert_eq!(columns[3].expr, "u.*");
+
+    Ok(())
+}
+
+#[test]
+fn test_result_columns() -> Result<()> {
+    let columns = lialoonk_sql_query_parser::result_columns(
+        "SELECT id, name AS label FROM users UNION SELECT id, title FROM posts",
+    )?;
+    let names: Vec<Option<&str>> = columns
+        .iter()
+        .map(|column| column.name.as_deref())
+        .collect();
+    assert_eq!(names, vec![Some("id"), Some("label")]);
+
+    let columns = lialoonk_sql_query_parser::result_columns(
+        "WITH merged (key, value) AS (SELECT id, name FROM a UNION ALL SELECT id, title FROM b) \
+         SELECT * FROM merged",
+    )?;
+    assert_eq!(
+        columns,
+        vec![
+            OutputColumn {
+                name: Some("key".to_string()),
+                expr: "id".to_string(),
+                alias: Some("key".to_string()),
+            },
+            OutputColumn {
+                name: Some("value".to_string()),
+                expr: "name".to_string(),
+                alias: Some("value".to_string()),
+            },
+        ]
+    );
+
+    let columns = lialoonk_sql_query_parser::result_columns(
+        "SELECT * FROM (SELECT a, b FROM t UNION SELECT c, d FROM u) AS s",
+    )?;
+    assert_eq!(columns.len(), 2);
+    assert_eq!(columns[0].expr, "a");
+
+    Ok(())
+}
+
+#[test]
+fn test_duplicate_output_names() -> Result<()> {
+    assert_eq!(
+        lialoonk_sql_query_parser::duplicate_output_names("SELECT COUNT(*) AS c, c FROM t")?,
+        vec!["c".to_string()]
+    );
+    assert_eq!(
+        lialoonk_sql_query_parser::duplicate_output_names("SELECT a.id, b.id, max(x) FROM a, b")?,
+        vec!["id".to_string()]
+    );
+    assert!(
+        lialoonk_sql_query_parser::duplicate_output_names("SELECT id, name FROM t")?.is_empty()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_columns_in_arithmetic() -> Result<()> {
+    let columns = lialoonk_sql_query_parser::columns_in_arithmetic(
+        "SELECT price * qty + tax, name FROM orders WHERE id % 2 = 0",
+    )?;
+    let expected: HashSet<String> = ["price", "qty", "tax", "id"]
+        .iter()
+        .map(|column| column.to_string())
+        .collect();
+    assert_eq!(columns, expected);
+
+    let columns = lialoonk_sql_query_parser::columns_in_arithmetic(
+        "SELECT first_name || last_name, (a + b) * 2 FROM users",
+    )?;
+    let expected: HashSet<String> = ["a", "b"].iter().map(|c| c.to_string()).collect();
+    assert_eq!(columns, expected);
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_to_tree_outlives_input() -> Result<()> {
+    let input = String::from("SELECT id FROM users");
+    let tree = lialoonk_sql_query_parser::parse_to_tree(&input)?;
+    drop(input);
+
+    let sql = &tree.roots[0];
+    assert_eq!(sql.rule, Rule::sql);
+    assert_eq!(sql.span, 0..20);
+
+    let statement = &sql.children[0];
+    assert_eq!(statement.rule, Rule::statement);
+    assert_eq!(statement.text, "SELECT id FROM users");
+
+    Ok(())
+}
+
+#[test]
+fn test_redundant_distinct() -> Result<()> {
+    assert!(lialoonk_sql_query_parser::redundant_distinct(
+        "SELECT DISTINCT COUNT(*) FROM t"
+    )?);
+    assert!(!lialoonk_sql_query_parser::redundant_distinct(
+        "SELECT DISTINCT name FROM t"
+    )?);
+    assert!(!lialoonk_sql_query_parser::redundant_distinct(
+        "SELECT DISTINCT COUNT(*) FROM t GROUP BY name"
+    )?);
+
+    let metadata = lialoonk_sql_query_parser::analyze_sql("SELECT distinct_users FROM stats")?;
+    assert!(metadata.columns.contains("distinct_users"));
+
+    Ok(())
+}
+
+#[test]
+fn test_dialect_from_str() -> Result<()> {
+    assert_eq!("ansi".parse::<Dialect>(), Ok(Dialect::Generic));
+    assert_eq!("postgres".parse::<Dialect>(), Ok(Dialect::Postgres));
+    assert_eq!("MySQL".parse::<Dialect>(), Ok(Dialect::MySql));
+    assert_eq!("sqlite".parse::<Dialect>(), Ok(Dialect::Sqlite));
+    assert_eq!("mssql".parse::<Dialect>(), Ok(Dialect::MsSql));
+
+    let err = "oracle".parse::<Dialect>().unwrap_err();
+    assert!(err.contains("unknown dialect 'oracle'"));
+
+    Ok(())
+}
+
+#[test]
+fn test_max_or_chain_length() -> Result<()> {
+    assert_eq!(
+        lialoonk_sql_query_parser::max_or_chain_length(
+            "SELECT id FROM t WHERE status = 1 OR status = 2 OR status = 3"
+        )?,
+        3
+    );
+    assert_eq!(
+        lialoonk_sql_query_parser::max_or_chain_length(
+            "SELECT id FROM t WHERE a = 1 OR b = 2 OR a = 3 OR c > 4"
+        )?,
+        2
+    );
+    assert_eq!(
+        lialoonk_sql_query_parser::max_or_chain_length("SELECT id FROM t WHERE a > 1")?,
+        0
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_symbols_at() -> Result<()> {
+    let query = "SELECT u.id FROM users u";
+
+    let alias = lialoonk_sql_query_parser::symbols_at(query, 7).context("no symbol at u")?;
+    assert_eq!(alias.name, "u");
+    assert_eq!(alias.kind, SymbolKind::TableAlias);
+    assert_eq!(alias.table.as_deref(), Some("users"));
+    assert_eq!(alias.span, 7..8);
+
+    let column = lialoonk_sql_query_parser::symbols_at(query, 9).context("no symbol at id")?;
+    assert_eq!(column.kind, SymbolKind::Column);
+    assert_eq!(column.table.as_deref(), Some("users"));
+
+    let table = lialoonk_sql_query_parser::symbols_at(query, 18).context("no symbol at users")?;
+    assert_eq!(table.kind, SymbolKind::Table);
+
+    assert!(lialoonk_sql_query_parser::symbols_at(query, 2).is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_set_op_tree_precedence() -> Result<()> {
+    let select = |sql: &str| Box::new(SetOpTree::Select(sql.to_string()));
+
+    let tree = lialoonk_sql_query_parser::set_op_tree(
+        "SELECT a FROM x UNION SELECT a FROM y INTERSECT SELECT a FROM z",
+    )?;
+    assert_eq!(
+        tree,
+        Some(SetOpTree::Operation {
+            operator: "UNION".to_string(),
+            left: select("SELECT a FROM x"),
+            right: Box::new(SetOpTree::Operation {
+                operator: "INTERSECT".to_string(),
+                left: select("SELECT a FROM y"),
+                right: select("SELECT a FROM z"),
+            }),
+        })
+    );
+
+    let tree = lialoonk_sql_query_parser::set_op_tree(
+        "SELECT a FROM x EXCEPT SELECT a FROM y UNION ALL SELECT a FROM z",
+    )?;
+    assert_eq!(
+        tree,
+        Some(SetOpTree::Operation {
+            operator: "UNION ALL".to_string(),
+            left: Box::new(SetOpTree::Operation {
+                operator: "EXCEPT".to_string(),
+                left: select("SELECT a FROM x"),
+                right: select("SELECT a FROM y"),
+            }),
+            right: select("SELECT a FROM z"),
+        })
+    );
+
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "SELECT a FROM x INTERSECT SELECT a FROM y EXCEPT SELECT a FROM z",
+    )?;
+    assert_eq!(metadata.tables.len(), 3);
+
+    Ok(())
+}
+
+#[test]
+fn test_has_random_order() -> Result<()> {
+    assert!(lialoonk_sql_query_parser::has_random_order(
+        "SELECT id FROM users ORDER BY RANDOM() LIMIT 10"
+    )?);
+    assert!(lialoonk_sql_query_parser::has_random_order(
+        "SELECT id FROM users ORDER BY name, newid()"
+    )?);
+    assert!(!lialoonk_sql_query_parser::has_random_order(
+        "SELECT id FROM users ORDER BY id"
+    )?);
+
+    Ok(())
+}
+
+#[test]
+fn analyze_options_store_normalized_sql() -> Result<()> {
+    let query = "select id\n  from users -- active only\n where active = true";
+
+    let metadata = lialoonk_sql_query_parser::analyze_sql_opts(query, &AnalyzeOptions::default())?;
+    assert_eq!(metadata.normalized_sql, None);
+
+    let options = AnalyzeOptions {
+        store_normalized: true,
+        ..AnalyzeOptions::default()
+    };
+    let metadata = lialoonk_sql_query_parser::analyze_sql_opts(query, &options)?;
+    assert_eq!(
+        metadata.normalized_sql.as_deref(),
+        Some("SELECT id FROM users WHERE active = TRUE")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_where_current_of() -> Result<()> {
+    let update = lialoonk_sql_query_parser::analyze_sql(
+        "UPDATE accounts SET balance = 0 WHERE CURRENT OF my_cursor",
+    )?;
+    assert_eq!(update.cursor.as_deref(), Some("my_cursor"));
+    assert!(!update.columns.contains("my_cursor"));
+    assert!(update.tables.contains("accounts"));
+
+    let delete =
+        lialoonk_sql_query_parser::analyze_sql("DELETE FROM accounts WHERE CURRENT OF c1")?;
+    assert_eq!(delete.cursor.as_deref(), Some("c1"));
+    assert!(delete.where_predicates.is_empty());
+
+    let filtered =
+        lialoonk_sql_query_parser::analyze_sql("DELETE FROM accounts WHERE current_balance = 0")?;
+    assert_eq!(filtered.cursor, None);
+    assert!(filtered.columns.contains("current_balance"));
+
+    Ok(())
+}
+
+#[test]
+fn test_leading_wildcard_likes() -> Result<()> {
+    assert_eq!(
+        lialoonk_sql_query_parser::leading_wildcard_likes(
+            "SELECT id FROM users WHERE name LIKE '%x' AND email NOT LIKE '_y%'"
+        )?,
+        vec![
+            "name LIKE '%x'".to_string(),
+            "email NOT LIKE '_y%'".to_string()
+        ]
+    );
+    assert!(lialoonk_sql_query_parser::leading_wildcard_likes(
+        "SELECT id FROM users WHERE name LIKE 'x%'"
+    )?
+    .is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_array_and_parameterized_casts() -> Result<()> {
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "SELECT x::int[], v::numeric(10,2), tags::text[] FROM items WHERE data::jsonb IS NOT NULL",
+    )?;
+    assert_eq!(
+        metadata.casts,
+        vec!["int[]", "numeric(10,2)", "text[]", "jsonb"]
+    );
+    assert!(metadata.columns.contains("x"));
+    assert!(metadata.columns.contains("data"));
+    assert!(!metadata.tables.contains("int"));
+
+    assert_eq!(
+        lialoonk_sql_query_parser::fingerprint("select x :: int [] from t")?,
+        "SELECT x::int[] FROM t"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cast_expressions() -> Result<()> {
+    use lialoonk_sql_query_parser::ast::{Expr, SelectItem, SetExpr, Statement};
+
+    let query = "SELECT CAST(price AS DECIMAL(10,2)) AS price, qty::numeric FROM items \
+                 WHERE cast(created_at AS date) = '2024-01-01'";
+    let metadata = lialoonk_sql_query_parser::analyze_sql(query)?;
+    assert_eq!(metadata.casts, vec!["DECIMAL(10,2)", "numeric", "date"]);
+    assert!(metadata.columns.contains("price"));
+    assert!(metadata.columns.contains("created_at"));
+    assert!(!metadata
+        .functions
+        .iter()
+        .any(|name| name.eq_ignore_ascii_case("cast")));
+
+    assert_eq!(
+        lialoonk_sql_query_parser::infer_output_types(query, &Default::default())?[0],
+        ("price".to_string(), Some("decimal(10,2)".to_string()))
+    );
+    assert_eq!(
+        lialoonk_sql_query_parser::fingerprint("select cast ( x as int ) from t")?,
+        "SELECT CAST(x AS int) FROM t"
+    );
+
+    let Statement::Select(select) = lialoonk_sql_query_parser::parse_ast(query)? else {
+        panic!("expected a SELECT");
+    };
+    let SetExpr::Select(select) = select.body else {
+        panic!("expected a plain SELECT");
+    };
+    assert!(matches!(
+        &select.projection[0],
+        SelectItem::Expr {
+            expr: Expr::Cast { data_type, .. },
+            ..
+        } if data_type == "DECIMAL(10,2)"
+    ));
+
+    assert!(
+        lialoonk_sql_query_parser::analyze_sql_with_dialect(query, Dialect::MySql).is_err(),
+        "`::` casts are PostgreSQL syntax"
+    );
+    assert!(lialoonk_sql_query_parser::analyze_sql_with_dialect(
+        "SELECT CAST(price AS DECIMAL(10,2)) FROM items",
+        Dialect::MySql
+    )
+    .is_ok());
+    Ok(())
+}
+
+#[test]
+fn test_rename_column() -> Result<()> {
+    let renamed = lialoonk_sql_query_parser::rename_column(
+        "SELECT u.email, o.email FROM users u JOIN orders o ON users.email = o.email",
+        "users",
+        "email",
+        "email_address",
+    )?;
+    assert_eq!(
+        renamed,
+        "SELECT u.email_address, o.email FROM users u JOIN orders o ON users.email_address = o.email"
+    );
+
+    let renamed = lialoonk_sql_query_parser::rename_column(
+        "SELECT email FROM users WHERE email LIKE '%@example.com'",
+        "users",
+        "email",
+        "email_address",
+    )?;
+    assert_eq!(
+        renamed,
+        "SELECT email_address FROM users WHERE email_address LIKE '%@example.com'"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_rename_column_quotes_keywords() -> Result<()> {
+    let renamed = lialoonk_sql_query_parser::rename_column(
+        "SELECT t.seq FROM t WHERE t.seq > 1",
+        "t",
+        "seq",
+        "order",
+    )?;
+    assert_eq!(renamed, "SELECT t.\"order\" FROM t WHERE t.\"order\" > 1");
+    assert!(lialoonk_sql_query_parser::parse_sql(&renamed).is_ok());
+
+    let renamed = lialoonk_sql_query_parser::rename_column_with(
+        "SELECT seq FROM t LIMIT 5",
+        "t",
+        "seq",
+        "order",
+        Dialect::MySql,
+    )?;
+    assert_eq!(renamed, "SELECT `order` FROM t LIMIT 5");
+
+    let renamed = lialoonk_sql_query_parser::rename_column_with(
+        "SELECT TOP 5 seq FROM t",
+        "t",
+        "seq",
+        "unit price",
+        Dialect::MsSql,
+    )?;
+    assert_eq!(renamed, "SELECT TOP 5 [unit price] FROM t");
+    assert!(lialoonk_sql_query_parser::analyze_sql_with_dialect(&renamed, Dialect::MsSql).is_ok());
+
+    assert_eq!(
+        lialoonk_sql_query_parser::quote_identifier("order", Dialect::MySql),
+        "`order`"
+    );
+    assert_eq!(
+        lialoonk_sql_query_parser::quote_identifier("unit price", Dialect::MsSql),
+        "[unit price]"
+    );
+    assert_eq!(
+        lialoonk_sql_query_parser::quote_identifier("say \"hi\"", Dialect::Postgres),
+        "\"say \"\"hi\"\"\""
+    );
+    assert_eq!(
+        lialoonk_sql_query_parser::quote_identifier("total", Dialect::Generic),
+        "total"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_group_by_expressions() -> Result<()> {
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "SELECT date_trunc('day', ts), COUNT(*) FROM events GROUP BY date_trunc('day', ts), kind",
+    )?;
+
+    assert_eq!(
+        metadata.group_by,
+        vec!["date_trunc('day', ts)".to_string(), "kind".to_string()]
+    );
+    assert!(metadata.functions.contains("date_trunc"));
+    assert!(metadata.columns.contains("ts"));
+    assert!(metadata.columns.contains("kind"));
+
+    Ok(())
+}
+
+#[test]
+fn test_where_uses_output_alias() -> Result<()> {
+    let flagged = lialoonk_sql_query_parser::where_uses_output_alias(
+        "SELECT a + b AS s, c AS c FROM t WHERE s > 0 AND c = 1 AND t.s < 5",
+    )?;
+    assert_eq!(flagged, vec!["s".to_string()]);
+
+    let flagged = lialoonk_sql_query_parser::where_uses_output_alias(
+        "SELECT a + b AS s FROM t WHERE a > 0 ORDER BY s",
+    )?;
+    assert!(flagged.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_sqlite_insert_or_replace() -> Result<()> {
+    let query = "INSERT OR REPLACE INTO users VALUES (1)";
+    let metadata = lialoonk_sql_query_parser::analyze_sql_with_dialect(query, Dialect::Sqlite)?;
+
+    assert_eq!(metadata.insert_or_action.as_deref(), Some("REPLACE"));
+    assert!(metadata.is_upsert);
+    assert!(metadata.tables.contains("users"));
+    assert!(lialoonk_sql_query_parser::analyze_sql_with_dialect(query, Dialect::Postgres).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_sqlite_insert_or_ignore() -> Result<()> {
+    let metadata = lialoonk_sql_query_parser::analyze_sql_with_dialect(
+        "INSERT or ignore INTO users VALUES (1)",
+        Dialect::Sqlite,
+    )?;
+
+    assert_eq!(metadata.insert_or_action.as_deref(), Some("IGNORE"));
+    assert!(!metadata.is_upsert);
+
+    let metadata = lialoonk_sql_query_parser::analyze_sql("INSERT INTO users VALUES (1)")?;
+    assert_eq!(metadata.insert_or_action, None);
+
+    Ok(())
+}
+
+#[test]
+fn test_single_row_lookup() -> Result<()> {
+    let metadata = lialoonk_sql_query_parser::analyze_sql("SELECT * FROM t WHERE id = ? LIMIT 1")?;
+    assert_eq!(metadata.limit, Some(1));
+    assert!(lialoonk_sql_query_parser::is_single_row_lookup(&metadata));
+
+    let metadata =
+        lialoonk_sql_query_parser::analyze_sql("SELECT name FROM users WHERE users.id = $1")?;
+    assert!(lialoonk_sql_query_parser::is_single_row_lookup(&metadata));
+
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "SELECT COUNT(*) FROM orders WHERE customer_id = ?",
+    )?;
+    assert!(!lialoonk_sql_query_parser::is_single_row_lookup(&metadata));
+
+    let metadata = lialoonk_sql_query_parser::analyze_sql("SELECT * FROM t WHERE id = 5")?;
+    assert!(!lialoonk_sql_query_parser::is_single_row_lookup(&metadata));
+
+    Ok(())
+}
+
+#[test]
+fn test_comments_between_keywords() -> Result<()> {
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "SELECT a FROM t LEFT /* keep rows */ OUTER JOIN u ON t.id = u.id",
+    )?;
+    assert_eq!(metadata.joins[0].join_type.as_deref(), Some("LEFT OUTER"));
+
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "SELECT a, COUNT(*) FROM t GROUP /* x */ BY a ORDER -- by name\n BY a",
+    )?;
+    assert_eq!(metadata.group_by, vec!["a".to_string()]);
+    assert_eq!(metadata.order_by.len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_equi_join_classification() -> Result<()> {
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "SELECT * FROM a JOIN b ON a.id = b.a_id\n   AND a.kind = 'x'",
+    )?;
+    let join = &metadata.joins[0];
+    assert_eq!(join.normalized_condition, "a.id = b.a_id AND a.kind = 'x'");
+    assert_eq!(
+        join.on_columns,
+        vec![("a.id".to_string(), "b.a_id".to_string())]
+    );
+    assert!(join.is_equi_join());
+    let restored: lialoonk_sql_query_parser::JoinInfo =
+        serde_json::from_str(&serde_json::to_string(join)?)?;
+    assert!(restored.is_equi_join());
+
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "SELECT * FROM a JOIN b ON a.ts BETWEEN b.start AND b.end",
+    )?;
+    assert!(!metadata.joins[0].is_equi_join());
+
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "SELECT * FROM a JOIN b ON a.id = b.id OR a.alt_id = b.id",
+    )?;
+    assert!(!metadata.joins[0].is_equi_join());
+
+    Ok(())
+}
+
+#[test]
+fn test_aggregate_usage_report() -> Result<()> {
+    let usage = lialoonk_sql_query_parser::aggregate_usage_report(
+        "SELECT dept, SUM(salary) FROM staff GROUP BY dept HAVING COUNT(*) > 3 ORDER BY dept",
+    )?;
+    assert_eq!(
+        usage,
+        lialoonk_sql_query_parser::AggUsage {
+            in_projection: true,
+            in_having: true,
+            in_order_by: false,
+            has_group_by: true,
+        }
+    );
+
+    let usage = lialoonk_sql_query_parser::aggregate_usage_report(
+        "SELECT name FROM staff WHERE id IN (SELECT MAX(id) FROM staff)",
+    )?;
+    assert_eq!(usage, lialoonk_sql_query_parser::AggUsage::default());
+
+    Ok(())
+}
+
+#[test]
+fn test_sanitize_with_placeholder_styles() -> Result<()> {
+    use lialoonk_sql_query_parser::PlaceholderStyle;
+
+    let query = "SELECT * FROM t WHERE a = 'x' AND b > 10";
+    assert_eq!(
+        lialoonk_sql_query_parser::sanitize_with(query, PlaceholderStyle::Dollar)?,
+        "SELECT * FROM t WHERE a = $1 AND b > $2"
+    );
+    assert_eq!(
+        lialoonk_sql_query_parser::sanitize_with(query, PlaceholderStyle::Colon)?,
+        "SELECT * FROM t WHERE a = :p1 AND b > :p2"
+    );
+    assert_eq!(
+        lialoonk_sql_query_parser::sanitize_with(query, PlaceholderStyle::Question)?,
+        lialoonk_sql_query_parser::sanitize_for_tracing(query)?
+    );
+    assert_eq!(
+        lialoonk_sql_query_parser::sanitize_with(
+            "SELECT * FROM t WHERE a = $1 AND b = 5",
+            PlaceholderStyle::Dollar
+        )?,
+        "SELECT * FROM t WHERE a = $1 AND b = $2"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_normalize_sql_groups_query_shapes() -> Result<()> {
+    let query = "SELECT id FROM Users u  -- lookup\n WHERE u.name = 'bob' AND age > 30 AND id = $1";
+    let first = lialoonk_sql_query_parser::normalize_sql(query)?;
+    let second = lialoonk_sql_query_parser::normalize_sql(
+        "select id from Users u where u.name='alice' and age>5 and id=$1",
+    )?;
+
+    assert_eq!(
+        first.shape,
+        "select id from Users u where u.name = ? and age > ? and id = $1"
+    );
+    assert_eq!(
+        lialoonk_sql_query_parser::sanitize_for_tracing(query)?,
+        "SELECT id FROM Users u WHERE u.name = ? AND age > ? AND id = $1"
+    );
+    assert_eq!(first, second);
+
+    let other = lialoonk_sql_query_parser::normalize_sql("SELECT id FROM Users u")?;
+    assert_ne!(other.hash, first.hash);
+    // FNV-1a of "select ?", so hashes stay comparable across versions and platforms
+    assert_eq!(
+        lialoonk_sql_query_parser::normalize_sql("SELECT 1")?.hash,
+        0x02fb_7a5a_1a5a_9a58
+    );
+    Ok(())
+}
+
+#[test]
+fn test_materialized_view_lineage() -> Result<()> {
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "CREATE MATERIALIZED VIEW order_totals AS \
+         WITH paid AS (SELECT * FROM payments) \
+         SELECT o.user_id, SUM(p.amount) FROM orders o JOIN paid p ON p.order_id = o.id \
+         JOIN users u ON u.id = o.user_id GROUP BY o.user_id",
+    )?;
+    let view = metadata.view.expect("view metadata");
+
+    assert_eq!(view.name, "order_totals");
+    assert!(view.materialized);
+    assert_eq!(
+        view.source_tables,
+        HashSet::from(["orders", "payments", "users"].map(String::from))
+    );
+
+    let metadata = lialoonk_sql_query_parser::analyze_sql("CREATE VIEW v AS SELECT id FROM t")?;
+    assert!(!metadata.view.expect("view metadata").materialized);
+    Ok(())
+}
+
+#[test]
+fn test_create_view_check_option() -> Result<()> {
+    use lialoonk_sql_query_parser::QueryKind;
+
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "CREATE VIEW active_users (id, name) AS SELECT id, name FROM users WHERE active = TRUE \
+         WITH CHECK OPTION",
+    )?;
+    assert_eq!(metadata.kind, QueryKind::CreateView);
+    assert!(metadata.tables.contains("users"));
+    assert!(metadata.columns.contains("active"));
+    let view = metadata.view.expect("view metadata");
+    assert_eq!(view.name, "active_users");
+    assert_eq!(view.columns, vec!["id".to_string(), "name".to_string()]);
+    assert_eq!(view.check_option.as_deref(), Some("CASCADED"));
+
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "CREATE VIEW v AS SELECT id FROM t WITH cascaded CHECK OPTION",
+    )?;
+    assert_eq!(
+        metadata.view.and_then(|view| view.check_option).as_deref(),
+        Some("CASCADED")
+    );
+
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "CREATE VIEW v AS SELECT id FROM t WITH LOCAL CHECK OPTION",
+    )?;
+    assert_eq!(
+        metadata.view.and_then(|view| view.check_option).as_deref(),
+        Some("LOCAL")
+    );
+
+    let metadata = lialoonk_sql_query_parser::analyze_sql("CREATE VIEW v AS SELECT id FROM t")?;
+    assert_eq!(metadata.view.and_then(|view| view.check_option), None);
+
+    Ok(())
+}
+
+#[test]
+fn test_exists_select_star() -> Result<()> {
+    let flagged = lialoonk_sql_query_parser::exists_select_star(
+        "SELECT id FROM users u WHERE EXISTS (SELECT * FROM t WHERE t.user_id = u.id) \
+         AND NOT EXISTS (SELECT 1 FROM bans) AND EXISTS (SELECT a, b FROM s)",
+    )?;
+
+    assert_eq!(
+        flagged,
+        vec![
+            "EXISTS (SELECT * FROM t WHERE t.user_id = u.id)".to_string(),
+            "EXISTS (SELECT a, b FROM s)".to_string(),
+        ]
+    );
+    assert!(lialoonk_sql_query_parser::exists_select_star(
+        "SELECT 1 WHERE EXISTS (SELECT 1 FROM t)"
+    )?
+    .is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_infer_output_types() -> Result<()> {
+    let schema = lialoonk_sql_query_parser::Schema::from([
+        ("users.id".to_string(), "bigint".to_string()),
+        ("users.name".to_string(), "text".to_string()),
+        ("orders.total".to_string(), "integer".to_string()),
+    ]);
+
+    let types = lialoonk_sql_query_parser::infer_output_types(
+        "SELECT u.name, id, COUNT(*) AS n, 'x' AS tag, SUM(o.total), price::numeric(10,2), \
+         UPPER(name) FROM users u JOIN orders o ON u.id = o.user_id",
+        &schema,
+    )?;
+
+    assert_eq!(
+        types,
+        vec![
+            ("name".to_string(), Some("text".to_string())),
+            ("id".to_string(), Some("bigint".to_string())),
+            ("n".to_string(), Some("bigint".to_string())),
+            ("tag".to_string(), Some("text".to_string())),
+            ("SUM".to_string(), Some("bigint".to_string())),
+            (
+                "price::numeric(10,2)".to_string(),
+                Some("numeric(10,2)".to_string())
+            ),
+            ("UPPER".to_string(), None),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_analyze_into_accumulates() -> Result<()> {
+    let mut metadata = lialoonk_sql_query_parser::QueryMetadata::default();
+
+    lialoonk_sql_query_parser::analyze_into(
+        "SELECT u.id FROM users u JOIN orders o ON u.id = o.user_id",
+        &mut metadata,
+    )?;
+    lialoonk_sql_query_parser::analyze_into("INSERT INTO audit_log VALUES ('x')", &mut metadata)?;
+    assert!(lialoonk_sql_query_parser::analyze_into("SELECT FROM WHERE", &mut metadata).is_err());
+
+    assert_eq!(
+        metadata.tables,
+        HashSet::from(["users".to_string(), "audit_log".to_string()])
+    );
+    assert_eq!(metadata.joins.len(), 1);
+    assert_eq!(metadata.literals, vec!["'x'".to_string()]);
+    assert_eq!(metadata.kind, QueryKind::Insert);
+
+    Ok(())
+}
+
+#[test]
+fn test_qualified_function_names() -> Result<()> {
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "SELECT myschema.sum(x), pg_catalog.upper(name) FROM t",
+    )?;
+
+    assert!(metadata.functions.contains("myschema.sum"));
+    assert!(metadata.functions.contains("pg_catalog.upper"));
+    assert!(metadata.aggregates.contains("myschema.sum"));
+    assert!(!metadata.aggregates.contains("pg_catalog.upper"));
+    assert!(metadata.columns.contains("x"));
+    assert_eq!(
+        lialoonk_sql_query_parser::fingerprint("select myschema.sum(x) from t")?,
+        "SELECT myschema.sum(x) FROM t"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_duplicate_unaliased_tables() -> Result<()> {
+    let flagged = lialoonk_sql_query_parser::duplicate_unaliased_tables(
+        "SELECT * FROM orders, customers JOIN orders ON orders.customer_id = customers.id",
+    )?;
+    assert_eq!(flagged, vec!["orders".to_string()]);
+
+    let flagged = lialoonk_sql_query_parser::duplicate_unaliased_tables(
+        "SELECT * FROM employees e JOIN employees m ON e.manager_id = m.id",
+    )?;
+    assert!(flagged.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_span_to_line_col() {
+    let input = "SELECT 'é', id\r\nFROM t";
+
+    let id = input.find("id").unwrap_or_default();
+    assert_eq!(
+        lialoonk_sql_query_parser::span_to_line_col(input, (id, id + 2)),
+        ((1, 13), (1, 15))
+    );
+
+    let from = input.find("FROM").unwrap_or_default();
+    assert_eq!(
+        lialoonk_sql_query_parser::span_to_line_col(input, (from, input.len())),
+        ((2, 1), (2, 7))
+    );
+}
+
+#[test]
+fn test_in_list_sizes() -> Result<()> {
+    let sizes = lialoonk_sql_query_parser::in_list_sizes(
+        "SELECT * FROM t WHERE id IN (1,2,3) AND status NOT IN ('a', 'b') \
+         AND owner IN (SELECT id FROM users)",
+    )?;
+
+    assert_eq!(
+        sizes,
+        vec![("id".to_string(), 3), ("status".to_string(), 2)]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_postgres_regex_operators() -> Result<()> {
+    let query =
+        "SELECT id FROM users WHERE name ~ '^a' AND email !~* 'x' AND code NOT SIMILAR TO 'b%'";
+    let metadata = lialoonk_sql_query_parser::analyze_sql_with_dialect(query, Dialect::Postgres)?;
+
+    assert!(metadata.columns.contains("name"));
+    assert!(metadata.columns.contains("email"));
+    assert_eq!(
+        metadata.where_predicates,
+        vec![
+            Predicate {
+                left: "name".to_string(),
+                operator: "~".to_string(),
+                right: Some("'^a'".to_string()),
+            },
+            Predicate {
+                left: "email".to_string(),
+                operator: "!~*".to_string(),
+                right: Some("'x'".to_string()),
+            },
+            Predicate {
+                left: "code".to_string(),
+                operator: "NOT SIMILAR TO".to_string(),
+                right: Some("'b%'".to_string()),
+            },
+        ]
+    );
+    assert!(lialoonk_sql_query_parser::analyze_sql_with_dialect(query, Dialect::MySql).is_err());
+
+    let options = AnalyzeOptions {
+        dialect: Dialect::Postgres,
+        store_normalized: true,
+        ..AnalyzeOptions::default()
+    };
+    let normalized = lialoonk_sql_query_parser::analyze_sql_opts(
+        "select id from users where email!~*'x'",
+        &options,
+    )?
+    .normalized_sql;
+    assert_eq!(
+        normalized.as_deref(),
+        Some("SELECT id FROM users WHERE email !~* 'x'")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_is_deterministic() -> Result<()> {
+    let deterministic = |query: &str| -> Result<bool> {
+        let metadata = lialoonk_sql_query_parser::analyze_sql(query)?;
+        Ok(lialoonk_sql_query_parser::is_deterministic(&metadata))
+    };
+
+    assert!(deterministic("SELECT id FROM t WHERE id = 1")?);
+    assert!(deterministic("SELECT UPPER(name) FROM t")?);
+    assert!(!deterministic("SELECT NOW()")?);
+    assert!(!deterministic(
+        "SELECT id FROM t WHERE created_at > CURRENT_TIMESTAMP"
+    )?);
+    assert!(!deterministic("SELECT pg_catalog.random()")?);
+    assert!(!deterministic("DELETE FROM t WHERE id = 1")?);
+
+    Ok(())
+}
+
+#[test]
+fn test_unsupported_construct_errors() {
+    let options = AnalyzeOptions::default();
+
+    let query = "UPDATE users SET name = 'x' WHERE id = 1 RETURNING id";
+    let err = lialoonk_sql_query_parser::analyze_sql_opts(query, &options).unwrap_err();
+    assert!(matches!(
+        err,
+        ParseError::Unsupported { ref feature, ref span }
+            if feature == "RETURNING clauses" && &query[span.clone()] == "RETURNING"
+    ));
+    assert_eq!(
+        err.to_string(),
+        "unsupported SQL construct: RETURNING clauses"
+    );
+
+    let err = lialoonk_sql_query_parser::parse_to_tree("SELECT id FROM t for update").unwrap_err();
+    assert!(matches!(
+        err,
+        ParseError::Unsupported { ref feature, .. } if feature == "row locking clauses (FOR UPDATE)"
+    ));
+
+    let query = "SELECT id FROM t WHERE id > 1 WINDOW w AS (ORDER BY id)";
+    let err = lialoonk_sql_query_parser::analyze_sql_opts(query, &options).unwrap_err();
+    assert!(matches!(
+        err,
+        ParseError::Unsupported { ref feature, ref span }
+            if feature == "named windows (WINDOW)" && &query[span.clone()] == "WINDOW"
+    ));
+
+    let err = lialoonk_sql_query_parser::analyze_sql_opts(
+        "MERGE INTO t USING s ON t.id = s.id WHEN MATCHED THEN DELETE",
+        &options,
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err,
+        ParseError::Unsupported { ref feature, .. } if feature == "MERGE statements"
+    ));
+
+    let err =
+        lialoonk_sql_query_parser::analyze_sql_opts("SELECT FROM WHERE", &options).unwrap_err();
+    assert!(matches!(err, ParseError::Syntax(_)));
+
+    let err = lialoonk_sql_query_parser::analyze_sql_opts("SELECT id FROM t OVER (x)", &options)
+        .unwrap_err();
+    assert!(matches!(err, ParseError::Syntax(_)));
+}
+
+#[test]
+fn test_bom_and_unicode_whitespace() -> Result<()> {
+    let query = "\u{FEFF}SELECT id FROM users";
+    let metadata = lialoonk_sql_query_parser::analyze_sql(query)?;
+    assert!(metadata.tables.contains("users"));
+    assert_eq!(
+        lialoonk_sql_query_parser::fingerprint(query)?,
+        "SELECT id FROM users"
+    );
+
+    let metadata =
+        lialoonk_sql_query_parser::analyze_sql("SELECT\u{00A0}id FROM\u{00A0}users WHERE id = 1")?;
+    assert!(metadata.tables.contains("users"));
+    assert!(metadata.columns.contains("id"));
+
+    Ok(())
+}
+
+#[test]
+fn test_column_usage_counts() -> Result<()> {
+    let metas = [
+        lialoonk_sql_query_parser::analyze_sql("SELECT id, name FROM users WHERE id = 1")?,
+        lialoonk_sql_query_parser::analyze_sql(
+            "SELECT u.id, o.total FROM users u JOIN orders o ON u.id = o.user_id",
+        )?,
     ];
 
-    for (rule, input) in cases {
-        assert_rule(rule, input)?;
+    let counts = lialoonk_sql_query_parser::column_usage_counts(&metas);
+
+    assert_eq!(counts.get("users.id"), Some(&4));
+    assert_eq!(counts.get("users.name"), Some(&1));
+    assert_eq!(counts.get("orders.total"), Some(&1));
+    assert_eq!(counts.get("orders.user_id"), Some(&1));
+
+    Ok(())
+}
+
+#[test]
+fn test_is_count_query() -> Result<()> {
+    assert!(lialoonk_sql_query_parser::is_count_query(
+        "SELECT COUNT(*) FROM t"
+    )?);
+    assert!(lialoonk_sql_query_parser::is_count_query(
+        "SELECT count(1) FROM t WHERE active = TRUE"
+    )?);
+    assert!(lialoonk_sql_query_parser::is_count_query(
+        "SELECT COUNT(DISTINCT x) FROM t"
+    )?);
+    assert!(!lialoonk_sql_query_parser::is_count_query(
+        "SELECT COUNT(*), dept FROM staff GROUP BY dept"
+    )?);
+    assert!(!lialoonk_sql_query_parser::is_count_query(
+        "SELECT COUNT(*) FROM staff GROUP BY dept"
+    )?);
+    assert!(!lialoonk_sql_query_parser::is_count_query(
+        "SELECT SUM(x) FROM t"
+    )?);
+    assert!(!lialoonk_sql_query_parser::is_count_query(
+        "SELECT COUNT(*) FROM a UNION ALL SELECT COUNT(*) FROM b"
+    )?);
+    assert!(!lialoonk_sql_query_parser::is_count_query(
+        "CREATE VIEW v AS SELECT COUNT(*) FROM t"
+    )?);
+
+    Ok(())
+}
+
+#[test]
+fn test_print_tree_shows_spans() -> Result<()> {
+    let input = "SELECT id FROM users";
+    let tree = lialoonk_sql_query_parser::print_tree(input)?;
+
+    assert!(tree.contains("select_stmt [0..20]"));
+    assert!(tree.lines().any(|line| line
+        .trim_start()
+        .starts_with("qualified_identifier [15..20]")
+        && line.ends_with("\"users\"")));
+    assert!(tree.lines().any(|line| line.starts_with("  ")));
+    Ok(())
+}
+
+#[test]
+fn test_operator_histogram() -> Result<()> {
+    let histogram =
+        lialoonk_sql_query_parser::operator_histogram("SELECT * FROM t WHERE a = 1 AND b = 2")?;
+    assert_eq!(histogram.len(), 2);
+    assert_eq!(histogram["="], 2);
+    assert_eq!(histogram["AND"], 1);
+
+    let histogram = lialoonk_sql_query_parser::operator_histogram(
+        "SELECT * FROM t WHERE a not  in (1, 2) OR b BETWEEN 1 AND 5 OR c IS NOT NULL OR NOT d LIKE 'x%'",
+    )?;
+    assert_eq!(histogram["NOT IN"], 1);
+    assert_eq!(histogram["BETWEEN"], 1);
+    assert_eq!(histogram["IS NOT NULL"], 1);
+    assert_eq!(histogram["LIKE"], 1);
+    assert_eq!(histogram["NOT"], 1);
+    assert_eq!(histogram["OR"], 3);
+    assert!(!histogram.contains_key("AND"));
+    Ok(())
+}
+
+#[test]
+fn test_stray_semicolons_around_single_statement() -> Result<()> {
+    for input in [
+        "SELECT id FROM users ;  ",
+        "  ; SELECT id FROM users",
+        "\n;\n SELECT id FROM users;;\n",
+        "\t SELECT id FROM users \n",
+    ] {
+        let metadata = lialoonk_sql_query_parser::analyze_sql(input)?;
+        assert!(metadata.tables.contains("users"), "{input:?}");
     }
 
+    assert!(lialoonk_sql_query_parser::analyze_sql(";").is_err());
+    assert!(lialoonk_sql_query_parser::analyze_sql("SELECT 1; SELECT 2").is_err());
     Ok(())
 }
 
 #[test]
-fn select_stmt_with_join_and_filters() -> Result<()> {
-    assert_rule(
-        Rule::select_stmt,
-        "SELECT DISTINCT u.id, name \
-         FROM users u JOIN posts p ON u.id = p.user_id \
-         WHERE p.kind = 'blog' \
-         GROUP BY u.id, name \
-         HAVING COUNT(p.id) > 1 \
-         ORDER BY u.id DESC \
-         LIMIT 10",
-    )
+fn test_parse_ast_select() -> Result<()> {
+    use lialoonk_sql_query_parser::ast::{
+        BinaryOperator, Expr, JoinType, Literal, SelectItem, SetExpr, Statement, TableFactor,
+    };
+
+    let statement = lialoonk_sql_query_parser::parse_ast(
+        "SELECT u.id, COUNT(*) AS n FROM users u LEFT JOIN orders o ON o.user_id = u.id \
+         WHERE u.age > 18 AND u.name LIKE 'a%' GROUP BY u.id LIMIT 10",
+    )?;
+    let Statement::Select(query) = statement else {
+        panic!("expected a SELECT, got {statement:?}");
+    };
+    let SetExpr::Select(select) = query.body else {
+        panic!("expected a plain SELECT");
+    };
+
+    assert_eq!(select.projection.len(), 2);
+    assert!(matches!(
+        &select.projection[1],
+        SelectItem::Expr { expr: Expr::Function(function), alias: Some(alias) }
+            if function.name == "COUNT" && function.wildcard && alias == "n"
+    ));
+    assert_eq!(
+        select.from,
+        vec![TableFactor::Table {
+            name: "users".to_string(),
+            alias: Some("u".to_string()),
+        }]
+    );
+    assert_eq!(select.joins[0].join_type, JoinType::Left);
+
+    let Some(Expr::Binary { left, op, right }) = select.selection else {
+        panic!("expected a binary WHERE condition");
+    };
+    assert_eq!(op, BinaryOperator::And);
+    assert!(matches!(
+        *left,
+        Expr::Binary {
+            op: BinaryOperator::Gt,
+            ..
+        }
+    ));
+    assert!(matches!(
+        *right,
+        Expr::Binary { op: BinaryOperator::Like, right, .. }
+            if *right == Expr::Literal(Literal::String("a%".to_string()))
+    ));
+    assert_eq!(select.group_by.len(), 1);
+    assert_eq!(select.limit, Some(10));
+    Ok(())
 }
 
 #[test]
-fn insert_update_delete_statements() -> Result<()> {
-    assert_rule(Rule::insert_stmt, "INSERT INTO metrics VALUES (sum(value))")?;
-    assert_rule(
-        Rule::update_stmt,
-        "UPDATE users SET name = 'Alice', age = 42 WHERE id = 10",
+fn test_parse_ast_with_dialect() -> Result<()> {
+    use lialoonk_sql_query_parser::ast::{SetExpr, Statement};
+    use lialoonk_sql_query_parser::parse_ast_with_dialect;
+
+    assert!(lialoonk_sql_query_parser::parse_ast("SELECT TOP 5 a FROM t").is_err());
+    let Statement::Select(query) = parse_ast_with_dialect("SELECT TOP 5 a FROM t", Dialect::MsSql)?
+    else {
+        panic!("expected a SELECT");
+    };
+    let SetExpr::Select(select) = query.body else {
+        panic!("expected a plain SELECT");
+    };
+    assert_eq!(select.limit, Some(5));
+
+    let Statement::Select(query) =
+        parse_ast_with_dialect("SELECT a FROM t ORDER BY a USING >", Dialect::Postgres)?
+    else {
+        panic!("expected a SELECT");
+    };
+    let SetExpr::Select(select) = query.body else {
+        panic!("expected a plain SELECT");
+    };
+    assert!(select.order_by[0].descending);
+
+    let Statement::Insert(insert) =
+        parse_ast_with_dialect("INSERT OR REPLACE INTO t VALUES (1)", Dialect::Sqlite)?
+    else {
+        panic!("expected an INSERT");
+    };
+    assert_eq!(insert.or_action.as_deref(), Some("REPLACE"));
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_ast_dml_and_set_operations() -> Result<()> {
+    use lialoonk_sql_query_parser::ast::{
+        Expr, FrameBound, FrameUnits, SelectItem, SetExpr, SetOperator, Statement, WindowFrame,
+    };
+
+    let statement =
+        lialoonk_sql_query_parser::parse_ast("UPDATE t SET a = 1, b = ? WHERE id = $1")?;
+    let Statement::Update(update) = statement else {
+        panic!("expected an UPDATE, got {statement:?}");
+    };
+    assert_eq!(update.table, "t");
+    assert_eq!(update.assignments.len(), 2);
+    assert_eq!(
+        update.assignments[1].value,
+        Expr::Parameter("?".to_string())
+    );
+
+    let statement =
+        lialoonk_sql_query_parser::parse_ast("DELETE FROM t WHERE id IN (SELECT id FROM s)")?;
+    let Statement::Delete(delete) = statement else {
+        panic!("expected a DELETE, got {statement:?}");
+    };
+    assert!(matches!(
+        delete.selection,
+        Some(Expr::InSubquery { negated: false, .. })
+    ));
+
+    let statement =
+        lialoonk_sql_query_parser::parse_ast("SELECT a FROM t UNION ALL SELECT b FROM s")?;
+    let Statement::Select(query) = statement else {
+        panic!("expected a SELECT, got {statement:?}");
+    };
+    assert!(matches!(
+        query.body,
+        SetExpr::SetOperation {
+            op: SetOperator::Union,
+            all: true,
+            ..
+        }
+    ));
+
+    let statement = lialoonk_sql_query_parser::parse_ast(
+        "SELECT SUM(x) OVER (ORDER BY d ROWS BETWEEN 2 PRECEDING AND CURRENT ROW) FROM t",
     )?;
-    assert_rule(
-        Rule::delete_stmt,
-        "DELETE FROM audit_logs WHERE created_at < '2024-01-01'",
+    let Statement::Select(query) = statement else {
+        panic!("expected a SELECT, got {statement:?}");
+    };
+    let SetExpr::Select(select) = query.body else {
+        panic!("expected a plain SELECT");
+    };
+    let SelectItem::Expr {
+        expr: Expr::Function(function),
+        ..
+    } = &select.projection[0]
+    else {
+        panic!("expected a function call");
+    };
+    let frame = function
+        .over
+        .as_ref()
+        .and_then(|window| window.frame.as_ref());
+    assert_eq!(
+        frame,
+        Some(&WindowFrame {
+            units: FrameUnits::Rows,
+            start: FrameBound::Preceding(2),
+            end: Some(FrameBound::CurrentRow),
+        })
+    );
+    Ok(())
+}
+
+#[test]
+fn test_scalar_subqueries() -> Result<()> {
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "SELECT id, (SELECT MAX(total) FROM orders WHERE orders.user_id = users.id) AS latest \
+         FROM users WHERE balance > (SELECT AVG(balance) FROM accounts)",
+    )?;
+    assert_eq!(metadata.tables.len(), 3);
+    assert!(metadata.tables.contains("orders"));
+    assert!(metadata.tables.contains("accounts"));
+    assert!(metadata.columns.contains("total"));
+    assert!(metadata.columns.contains("balance"));
+    assert!(metadata.aggregates.contains("MAX"));
+    assert!(metadata.aggregates.contains("AVG"));
+
+    let names: Vec<_> = lialoonk_sql_query_parser::output_columns(
+        "SELECT id, (SELECT MAX(total) FROM orders) AS latest FROM users",
+    )?
+    .into_iter()
+    .map(|column| column.name)
+    .collect();
+    assert_eq!(
+        names,
+        vec![Some("id".to_string()), Some("latest".to_string())]
+    );
+
+    // A parenthesized expression is not a subquery
+    let metadata = lialoonk_sql_query_parser::analyze_sql("SELECT (price + 1) * 2 FROM items")?;
+    assert!(metadata.columns.contains("price"));
+    Ok(())
+}
+
+#[test]
+fn test_case_expressions() -> Result<()> {
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "SELECT CASE WHEN status = 1 THEN 'active' ELSE fallback END FROM users",
+    )?;
+    assert!(metadata.columns.contains("status"));
+    assert!(metadata.columns.contains("fallback"));
+
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "SELECT case kind when 1 then a when 2 then b else c end AS label, casex FROM t",
+    )?;
+    for column in ["kind", "a", "b", "c", "casex"] {
+        assert!(metadata.columns.contains(column), "{column}");
+    }
+
+    let schema = lialoonk_sql_query_parser::Schema::from([("t.a".to_string(), "int".to_string())]);
+    let types = lialoonk_sql_query_parser::infer_output_types(
+        "SELECT CASE WHEN a > 0 THEN NULL ELSE 'none' END AS label FROM t",
+        &schema,
+    )?;
+    assert_eq!(types, vec![("label".to_string(), Some("text".to_string()))]);
+
+    let statement =
+        lialoonk_sql_query_parser::parse_ast("SELECT CASE kind WHEN 1 THEN 'one' END FROM t")?;
+    let lialoonk_sql_query_parser::ast::Statement::Select(query) = statement else {
+        panic!("expected a SELECT, got {statement:?}");
+    };
+    let lialoonk_sql_query_parser::ast::SetExpr::Select(select) = query.body else {
+        panic!("expected a plain SELECT");
+    };
+    assert!(matches!(
+        &select.projection[0],
+        lialoonk_sql_query_parser::ast::SelectItem::Expr {
+            expr: lialoonk_sql_query_parser::ast::Expr::Case {
+                operand: Some(_),
+                branches,
+                else_result: None,
+            },
+            ..
+        } if branches.len() == 1
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_schema_qualified_names() -> Result<()> {
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "SELECT o.id, analytics.public.orders.total FROM analytics.public.orders o \
+         JOIN sales.customers c ON o.customer_id = c.id WHERE c.active",
+    )?;
+
+    assert!(metadata.tables.contains("analytics.public.orders"));
+    assert_eq!(metadata.joins[0].table, "sales.customers");
+    assert!(metadata.schemas.contains("analytics.public"));
+    assert!(metadata.schemas.contains("sales"));
+    assert_eq!(metadata.schemas.len(), 2);
+    assert!(metadata
+        .qualified_columns
+        .iter()
+        .any(
+            |column| column.table.as_deref() == Some("analytics.public.orders")
+                && column.column == "total"
+        ));
+
+    let metadata =
+        lialoonk_sql_query_parser::analyze_sql("DROP TABLE IF EXISTS staging.events, logs")?;
+    assert!(metadata.tables.contains("staging.events"));
+    assert!(metadata.tables.contains("logs"));
+    assert_eq!(metadata.schemas.len(), 1);
+
+    assert!(lialoonk_sql_query_parser::parse_sql("SELECT * FROM a.b.c.d").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_quoted_identifiers_are_unquoted() -> Result<()> {
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "SELECT \"oi\".\"Order Id\", [unit price], `say \"\"hi` FROM \"Sales\".\"Order Items\" AS \"oi\" \
+         JOIN [dbo].[odd]]name] u ON u.id = \"oi\".\"Order Id\"",
+    )?;
+
+    assert!(metadata.tables.contains("Sales.Order Items"));
+    assert!(metadata.schemas.contains("Sales"));
+    assert_eq!(metadata.joins[0].table, "dbo.odd]name");
+    assert_eq!(
+        metadata.aliases.get("oi").map(String::as_str),
+        Some("Sales.Order Items")
+    );
+    assert!(metadata.columns.contains("oi.Order Id"));
+    assert!(metadata.columns.contains("unit price"));
+    assert!(metadata.columns.contains("say \"\"hi"));
+    assert!(metadata.quoted_identifiers.contains("Order Items"));
+    assert!(metadata
+        .qualified_columns
+        .iter()
+        .any(
+            |column| column.table.as_deref() == Some("Sales.Order Items")
+                && column.column == "Order Id"
+        ));
+
+    let query = "SELECT `id` FROM users";
+    assert!(lialoonk_sql_query_parser::analyze_sql_with_dialect(query, Dialect::MySql).is_ok());
+    assert!(lialoonk_sql_query_parser::analyze_sql_with_dialect(query, Dialect::Postgres).is_err());
+    let query = "SELECT [id] FROM users";
+    assert!(lialoonk_sql_query_parser::analyze_sql_with_dialect(query, Dialect::MsSql).is_ok());
+    assert!(lialoonk_sql_query_parser::analyze_sql_with_dialect(query, Dialect::MySql).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_analyze_into_unquotes_each_name_once() -> Result<()> {
+    let mut metadata = lialoonk_sql_query_parser::QueryMetadata::default();
+
+    lialoonk_sql_query_parser::analyze_into(
+        "SELECT \"\"\"id\"\"\" FROM \"\"\"x\"\"\" AS \"\"\"a\"\"\"",
+        &mut metadata,
+    )?;
+    lialoonk_sql_query_parser::analyze_into("SELECT \"Id\" FROM \"Y\"", &mut metadata)?;
+
+    assert_eq!(
+        metadata.tables,
+        HashSet::from(["\"x\"".to_string(), "Y".to_string()])
+    );
+    assert!(metadata.columns.contains("\"id\""));
+    assert!(metadata.columns.contains("Id"));
+    assert_eq!(
+        metadata.aliases.get("\"a\"").map(String::as_str),
+        Some("\"x\"")
+    );
+    assert_eq!(metadata.qualified_columns[0].column, "\"id\"");
+
+    Ok(())
+}
+
+#[test]
+fn test_analyze_options_select_dialect() -> Result<()> {
+    use lialoonk_sql_query_parser::analyze_sql_opts;
+
+    let mssql = AnalyzeOptions {
+        dialect: Dialect::MsSql,
+        ..AnalyzeOptions::default()
+    };
+    let metadata = analyze_sql_opts("SELECT DISTINCT TOP 5 [name] FROM users", &mssql)?;
+    assert_eq!(metadata.limit, Some(5));
+    assert!(metadata.columns.contains("name"));
+    assert!(analyze_sql_opts("SELECT name FROM users LIMIT 5", &mssql).is_err());
+    assert!(analyze_sql_opts("SELECT id::text FROM users", &mssql).is_err());
+
+    let postgres = AnalyzeOptions {
+        dialect: Dialect::Postgres,
+        ..AnalyzeOptions::default()
+    };
+    assert!(analyze_sql_opts("SELECT id::text FROM users LIMIT 5", &postgres).is_ok());
+    assert!(analyze_sql_opts("SELECT TOP 5 name FROM users", &postgres).is_err());
+    assert!(analyze_sql_opts("SELECT `name` FROM users", &postgres).is_err());
+
+    let metadata = analyze_sql_opts("SELECT top1 FROM users", &AnalyzeOptions::default())?;
+    assert!(metadata.columns.contains("top1"));
+    assert_eq!(metadata.limit, None);
+
+    assert!(matches!(
+        lialoonk_sql_query_parser::analyze_sql_with_dialect(
+            "SELECT id FROM users FOR UPDATE",
+            Dialect::MySql
+        ),
+        Err(ParseError::Unsupported { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_bind_parameter_metadata() -> Result<()> {
+    use lialoonk_sql_query_parser::{Parameter, ParameterStyle};
+
+    let query = "SELECT * FROM t WHERE a = ? AND b = $2 AND c IN (:name, @p, ?) LIMIT ?";
+    let metadata = lialoonk_sql_query_parser::analyze_sql(query)?;
+
+    assert_eq!(
+        metadata.parameters[..2],
+        [
+            Parameter {
+                style: ParameterStyle::Question,
+                name: None,
+                index: Some(1),
+                span: (26, 27),
+            },
+            Parameter {
+                style: ParameterStyle::Dollar,
+                name: None,
+                index: Some(2),
+                span: (36, 38),
+            },
+        ]
+    );
+    let styles: Vec<_> = metadata
+        .parameters
+        .iter()
+        .map(|param| param.style)
+        .collect();
+    assert_eq!(
+        styles,
+        [
+            ParameterStyle::Question,
+            ParameterStyle::Dollar,
+            ParameterStyle::Colon,
+            ParameterStyle::At,
+            ParameterStyle::Question,
+            ParameterStyle::Question,
+        ]
+    );
+    assert_eq!(metadata.parameters[2].name.as_deref(), Some("name"));
+    assert_eq!(metadata.parameters[3].name.as_deref(), Some("p"));
+    assert_eq!(metadata.parameters[5].index, Some(3));
+    let (start, end) = metadata.parameters[2].span;
+    assert_eq!(&query[start..end], ":name");
+    assert_eq!(metadata.limit, None);
+    Ok(())
+}
+
+#[test]
+fn test_is_predicates_and_null_safe_comparisons() -> Result<()> {
+    use lialoonk_sql_query_parser::ast::{BinaryOperator, Expr, SetExpr, Statement};
+
+    let query = "SELECT id FROM t WHERE deleted_at IS NOT NULL AND active IS TRUE \
+                 AND a IS NOT DISTINCT FROM b";
+    let metadata = lialoonk_sql_query_parser::analyze_sql(query)?;
+    let operators: Vec<_> = metadata
+        .where_predicates
+        .iter()
+        .map(|predicate| (predicate.operator.as_str(), predicate.right.as_deref()))
+        .collect();
+    assert_eq!(
+        operators,
+        [
+            ("IS NOT NULL", None),
+            ("IS TRUE", None),
+            ("IS NOT DISTINCT FROM", Some("b")),
+        ]
+    );
+
+    let Statement::Select(select) = lialoonk_sql_query_parser::parse_ast(query)? else {
+        panic!("expected a SELECT");
+    };
+    let SetExpr::Select(select) = select.body else {
+        panic!("expected a plain SELECT");
+    };
+    let Some(Expr::Binary { left, right, .. }) = select.selection else {
+        panic!("expected an AND chain");
+    };
+    assert!(matches!(*right, Expr::IsDistinctFrom { negated: true, .. }));
+    assert!(matches!(
+        *left,
+        Expr::Binary { right, .. } if matches!(*right, Expr::IsBoolean { value: true, negated: false, .. })
+    ));
+
+    let query = "SELECT id FROM t WHERE a <=> NULL";
+    let metadata = lialoonk_sql_query_parser::analyze_sql_with_dialect(query, Dialect::MySql)?;
+    assert_eq!(metadata.where_predicates[0].operator, "<=>");
+    assert!(matches!(
+        lialoonk_sql_query_parser::parse_ast(query)?,
+        Statement::Select(select) if matches!(
+            &select.body,
+            SetExpr::Select(select) if matches!(
+                select.selection,
+                Some(Expr::Binary { op: BinaryOperator::NullSafeEq, .. })
+            )
+        )
+    ));
+    assert!(lialoonk_sql_query_parser::analyze_sql_with_dialect(query, Dialect::Postgres).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_exists_subqueries_are_analyzed() -> Result<()> {
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "UPDATE orders SET flagged = TRUE WHERE NOT EXISTS (\
+            SELECT 1 FROM payments p JOIN refunds r ON r.payment_id = p.id \
+            WHERE p.order_id = orders.id LIMIT 1)",
+    )?;
+
+    assert!(metadata.tables.contains("orders"));
+    assert!(metadata.tables.contains("payments"));
+    assert_eq!(metadata.joins[0].table, "refunds");
+    assert_eq!(
+        metadata.aliases.get("p").map(String::as_str),
+        Some("payments")
+    );
+    assert!(metadata.columns.contains("p.order_id"));
+    assert!(metadata
+        .qualified_columns
+        .iter()
+        .any(|column| column.table.as_deref() == Some("payments") && column.column == "order_id"));
+    assert_eq!(
+        metadata.where_predicates[0].right.as_deref(),
+        Some("orders.id")
+    );
+    assert_eq!(metadata.limit, None);
+    Ok(())
+}
+
+#[test]
+fn test_resolved_columns() -> Result<()> {
+    use lialoonk_sql_query_parser::ColumnRef;
+
+    let column = |name: &str, qualifier: Option<&str>, table: Option<&str>| ColumnRef {
+        name: name.to_string(),
+        qualifier: qualifier.map(str::to_string),
+        table: table.map(str::to_string),
+    };
+
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "SELECT u.name, orders.total, status, t.n FROM users u \
+         JOIN orders ON orders.user_id = u.id \
+         JOIN (SELECT COUNT(*) AS n FROM items) t ON TRUE",
+    )?;
+    assert!(metadata
+        .resolved_columns
+        .contains(&column("name", Some("u"), Some("users"))));
+    assert!(metadata
+        .resolved_columns
+        .contains(&column("total", Some("orders"), Some("orders"))));
+    assert!(metadata
+        .resolved_columns
+        .contains(&column("status", None, None)));
+    assert!(metadata
+        .resolved_columns
+        .contains(&column("n", Some("t"), None)));
+
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "SELECT id, p.* FROM public.products p WHERE price > 1",
+    )?;
+    assert_eq!(
+        metadata.resolved_columns,
+        [
+            column("id", None, Some("public.products")),
+            column("price", None, Some("public.products")),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_cte_dependencies() -> Result<()> {
+    let dependencies = lialoonk_sql_query_parser::cte_dependencies(
+        "WITH a AS (SELECT id FROM x), b AS (SELECT a.id FROM a JOIN y ON a.id = y.id) \
+         SELECT * FROM b",
+    )?;
+
+    assert_eq!(dependencies.len(), 2);
+    assert_eq!(dependencies["a"], HashSet::from(["x".to_string()]));
+    assert_eq!(
+        dependencies["b"],
+        HashSet::from(["a".to_string(), "y".to_string()])
+    );
+
+    Ok(())
+}
+
+/// Replays every `.sql` file under `tests/corpus/`
+///
+/// A file must parse unless its first line is `-- expect-error: <text>`, in which case parsing
+/// must fail with an error message containing `<text>`.
+#[test]
+fn test_corpus_replay() -> Result<()> {
+    let corpus = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+    let mut files = std::fs::read_dir(&corpus)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    files.retain(|path| path.extension().is_some_and(|extension| extension == "sql"));
+    files.sort();
+    assert!(!files.is_empty(), "no corpus files in {}", corpus.display());
+
+    let mut failures = Vec::new();
+    for path in &files {
+        let input = std::fs::read_to_string(path)?;
+        let expected_error = input
+            .lines()
+            .next()
+            .and_then(|line| line.strip_prefix("-- expect-error:"))
+            .map(str::trim);
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+
+        match (lialoonk_sql_query_parser::parse_sql(&input), expected_error) {
+            (Ok(_), None) => {}
+            (Ok(_), Some(expected)) => {
+                failures.push(format!("{name}: parsed, expected error '{expected}'"))
+            }
+            (Err(error), None) => failures.push(format!("{name}: {error}")),
+            (Err(error), Some(expected)) if !error.to_string().contains(expected) => {
+                failures.push(format!("{name}: expected error '{expected}', got {error}"))
+            }
+            (Err(_), Some(_)) => {}
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "corpus files failed:\n{}",
+        failures.join("\n")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_concatenated_literal_predicates() -> Result<()> {
+    let flagged = lialoonk_sql_query_parser::concatenated_literal_predicates(
+        "SELECT id FROM users WHERE name = 'a' || x AND id = 1",
+    )?;
+    assert_eq!(flagged, vec!["name = 'a' || x".to_string()]);
+
+    let flagged = lialoonk_sql_query_parser::concatenated_literal_predicates(
+        "SELECT first || last FROM users WHERE name = ?",
+    )?;
+    assert!(flagged.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn chained_comparison_is_rejected() -> Result<()> {
+    let err =
+        lialoonk_sql_query_parser::analyze_sql("SELECT id FROM t WHERE 1 < x < 10").unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("chained comparison not allowed; use BETWEEN or AND"));
+
+    lialoonk_sql_query_parser::analyze_sql("SELECT id FROM t WHERE x BETWEEN 1 AND 10")?;
+    lialoonk_sql_query_parser::analyze_sql("SELECT id FROM t WHERE 1 < x AND x < 10")?;
+
+    Ok(())
+}
+
+#[test]
+fn test_optimizer_hint_comments() -> Result<()> {
+    let metadata = lialoonk_sql_query_parser::analyze_sql("SELECT /*+ FULL(t) */ * FROM t")?;
+    assert_eq!(metadata.hints, vec!["FULL(t)".to_string()]);
+    assert!(metadata.tables.contains("t"));
+
+    let metadata = lialoonk_sql_query_parser::analyze_sql(
+        "SELECT /* plain comment */ id FROM t WHERE note = '/*+ not a hint */'",
     )?;
+    assert!(metadata.hints.is_empty());
+    assert!(metadata.columns.contains("id"));
+
+    assert_eq!(
+        lialoonk_sql_query_parser::fingerprint("select /* c */ id from t")?,
+        "SELECT id FROM t"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn trailing_semicolon_is_ignored() -> Result<()> {
+    let with_semicolon = lialoonk_sql_query_parser::analyze_sql("SELECT id FROM users;")?;
+    let without = lialoonk_sql_query_parser::analyze_sql("SELECT id FROM users")?;
+
+    assert_eq!(with_semicolon, without);
+    assert!(lialoonk_sql_query_parser::analyze_sql("SELECT 1;").is_ok());
+
+    Ok(())
+}
+
+#[test]
+fn trailing_line_comment_is_accepted() -> Result<()> {
+    for query in [
+        "SELECT id FROM users -- trailing comment",
+        "SELECT id FROM users -- trailing comment\n",
+        "SELECT id FROM users; -- trailing comment",
+    ] {
+        let metadata = lialoonk_sql_query_parser::analyze_sql(query)?;
+        assert!(metadata.tables.contains("users"), "{query}");
+    }
+    assert_rule(Rule::COMMENT, "-- no newline")?;
+
     Ok(())
 }
 